@@ -0,0 +1,143 @@
+//! Throughput of each mode over a few buffer sizes. Run with `cargo bench`.
+
+use aes::{
+	cipher::{consts::U16, generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit},
+	Aes128, Aes256,
+};
+use aes_modes::{
+	cbc_encrypt_stream, ctr_decrypt_in_place, ctr_encrypt_stream, gcm_encrypt, gcm_verify, BlockMode, Cbc, Ctr, Ecb,
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+const KEY: [u8; 16] = *b"YELLOW SUBMARINE";
+
+const SIZES: [(&str, usize); 3] = [("1KB", 1 << 10), ("64KB", 64 << 10), ("1MB", 1 << 20)];
+
+const MODES: [(&str, &dyn BlockMode); 3] = [("ecb", &Ecb), ("cbc", &Cbc), ("ctr", &Ctr)];
+
+fn encrypt(c: &mut Criterion) {
+	let mut group = c.benchmark_group("encrypt");
+
+	for (size_name, size) in SIZES {
+		group.throughput(Throughput::Bytes(size as u64));
+		for (mode_name, mode) in MODES {
+			// The mode functions take the buffer by value, so each iteration gets a fresh copy,
+			// made outside the timed part.
+			group.bench_function(BenchmarkId::new(mode_name, size_name), |b| {
+				b.iter_batched(|| vec![0x42; size], |plain_text| mode.encrypt(plain_text, KEY), BatchSize::LargeInput)
+			});
+		}
+	}
+
+	group.finish();
+}
+
+fn decrypt(c: &mut Criterion) {
+	let mut group = c.benchmark_group("decrypt");
+
+	for (size_name, size) in SIZES {
+		group.throughput(Throughput::Bytes(size as u64));
+		for (mode_name, mode) in MODES {
+			let cipher_text = mode.encrypt(vec![0x42; size], KEY);
+			group.bench_function(BenchmarkId::new(mode_name, size_name), |b| {
+				b.iter_batched(|| cipher_text.clone(), |cipher_text| mode.decrypt(cipher_text, KEY), BatchSize::LargeInput)
+			});
+		}
+
+		// The same buffer decrypted over and over, with a nonce put back in front each time, so
+		// nothing is allocated. The data just turns to noise after the first pass, which doesn't
+		// change the work.
+		let mut buf = Ctr.encrypt(vec![0x42; size], KEY);
+		group.bench_function(BenchmarkId::new("ctr_in_place", size_name), |b| {
+			b.iter(|| {
+				buf.splice(0..0, [0; 8]);
+				ctr_decrypt_in_place(&mut buf, KEY).unwrap();
+			})
+		});
+	}
+
+	group.finish();
+}
+
+/// The streaming CBC and CTR encryptors, which XOR a whole block at a time with xor_arrays.
+fn stream(c: &mut Criterion) {
+	let mut group = c.benchmark_group("stream");
+
+	for (size_name, size) in SIZES {
+		group.throughput(Throughput::Bytes(size as u64));
+		let plain_text = vec![0x42; size];
+		group.bench_function(BenchmarkId::new("cbc", size_name), |b| {
+			b.iter(|| cbc_encrypt_stream(plain_text.as_slice(), Vec::with_capacity(size + 32), KEY).unwrap())
+		});
+		group.bench_function(BenchmarkId::new("ctr", size_name), |b| {
+			b.iter(|| ctr_encrypt_stream(plain_text.as_slice(), Vec::with_capacity(size + 8), KEY).unwrap())
+		});
+	}
+
+	group.finish();
+}
+
+/// GCM's authentication on its own, through gcm_verify, so GHASH isn't buried under the
+/// keystream. Run once with `--features table-ghash` and once without to compare the table
+/// multiplication against gf_mul; criterion reports the change between the two runs.
+fn ghash(c: &mut Criterion) {
+	let mut group = c.benchmark_group("ghash");
+
+	for (size_name, size) in SIZES {
+		group.throughput(Throughput::Bytes(size as u64));
+		let cipher_text = gcm_encrypt(vec![0x42; size], KEY, b"header");
+		group.bench_function(BenchmarkId::new("gcm_verify", size_name), |b| {
+			b.iter(|| gcm_verify(&cipher_text, KEY, b"header").unwrap())
+		});
+	}
+
+	group.finish();
+}
+
+/// What the mode functions save by expanding the key once per message instead of once per
+/// block, measured on the raw block cipher over 64 KB.
+fn key_schedule(c: &mut Criterion) {
+	let mut group = c.benchmark_group("key_schedule");
+	let blocks = vec![GenericArray::from([0x42u8; 16]); (64 << 10) / 16];
+	group.throughput(Throughput::Bytes((blocks.len() * 16) as u64));
+
+	key_schedule_for::<Aes128>(&mut group, "aes128", &blocks);
+	key_schedule_for::<Aes256>(&mut group, "aes256", &blocks);
+
+	group.finish();
+}
+
+fn key_schedule_for<C: KeyInit + BlockEncrypt<BlockSize = U16> + BlockDecrypt<BlockSize = U16>>(
+	group: &mut criterion::BenchmarkGroup<'_, criterion::measurement::WallTime>,
+	key_size: &str,
+	blocks: &[GenericArray<u8, U16>],
+) {
+	let key = GenericArray::default();
+	let run = |per_block: bool, decrypt: bool| {
+		let hoisted = C::new(&key);
+		let mut blocks = blocks.to_vec();
+		for block in &mut blocks {
+			let fresh;
+			let cipher = if per_block {
+				fresh = C::new(&key);
+				&fresh
+			} else {
+				&hoisted
+			};
+			if decrypt {
+				cipher.decrypt_block(block);
+			} else {
+				cipher.encrypt_block(block);
+			}
+		}
+		black_box(blocks)
+	};
+
+	for (direction, decrypt) in [("encrypt", false), ("decrypt", true)] {
+		group.bench_function(BenchmarkId::new(format!("{key_size}_{direction}"), "per_block"), |b| b.iter(|| run(true, decrypt)));
+		group.bench_function(BenchmarkId::new(format!("{key_size}_{direction}"), "hoisted"), |b| b.iter(|| run(false, decrypt)));
+	}
+}
+
+criterion_group!(benches, encrypt, decrypt, stream, ghash, key_schedule);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Encrypts and decrypts a message with CBC, printing the hex of each step.
+//!
+//! ```text
+//! cargo run --example cbc
+//! ```
+
+use aes_modes::{cbc_decrypt, cbc_encrypt, hex_encode, repeated_block_count, DecryptError, BLOCK_SIZE};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+fn main() {
+	// The same plaintext as the ecb example: chaining hides the repeated block.
+	let message = b"SIXTEEN BYTES!!!SIXTEEN BYTES!!! and then some";
+	let (cipher_text, plain_text) = round_trip(message).expect("cbc_encrypt output always decrypts");
+
+	println!("plaintext:  {}", hex_encode(message));
+	println!("IV:         {}", hex_encode(&cipher_text[..BLOCK_SIZE]));
+	println!("ciphertext: {}", hex_encode(&cipher_text[BLOCK_SIZE..]));
+	println!("repeated ciphertext blocks: {}", repeated_block_count(&cipher_text));
+	println!("decrypted:  {:?}", String::from_utf8_lossy(&plain_text));
+}
+
+/// Encrypts `message` and decrypts the result, returning both.
+fn round_trip(message: &[u8]) -> Result<(Vec<u8>, Vec<u8>), DecryptError> {
+	let cipher_text = cbc_encrypt(message.to_vec(), KEY);
+	let plain_text = cbc_decrypt(cipher_text.clone(), KEY)?;
+
+	Ok((cipher_text, plain_text))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn the_example_round_trips_and_hides_repeats() {
+		for message in [&b""[..], b"SIXTEEN BYTES!!!SIXTEEN BYTES!!!", b"and then some"] {
+			let (cipher_text, plain_text) = round_trip(message).unwrap();
+
+			assert_eq!(plain_text, message);
+			assert_eq!(cipher_text.len(), BLOCK_SIZE + (message.len() / BLOCK_SIZE + 1) * BLOCK_SIZE);
+			assert_eq!(repeated_block_count(&cipher_text), 0);
+		}
+	}
+}
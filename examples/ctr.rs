@@ -0,0 +1,42 @@
+//! Encrypts and decrypts a message with CTR, printing the hex of each step.
+//!
+//! ```text
+//! cargo run --example ctr
+//! ```
+
+use aes_modes::{ctr_decrypt, ctr_encrypt, hex_encode, DecryptError, BLOCK_SIZE};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+fn main() {
+	let message = b"CTR needs no padding, so this stays 45 bytes.";
+	let (cipher_text, plain_text) = round_trip(message).expect("ctr_encrypt output always decrypts");
+
+	println!("plaintext:  {}", hex_encode(message));
+	println!("nonce:      {}", hex_encode(&cipher_text[..BLOCK_SIZE / 2]));
+	println!("ciphertext: {}", hex_encode(&cipher_text[BLOCK_SIZE / 2..]));
+	println!("decrypted:  {:?}", String::from_utf8_lossy(&plain_text));
+}
+
+/// Encrypts `message` and decrypts the result, returning both.
+fn round_trip(message: &[u8]) -> Result<(Vec<u8>, Vec<u8>), DecryptError> {
+	let cipher_text = ctr_encrypt(message.to_vec(), KEY);
+	let plain_text = ctr_decrypt(cipher_text.clone(), KEY)?;
+
+	Ok((cipher_text, plain_text))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn the_example_round_trips_at_the_exact_length() {
+		for message in [&b""[..], b"CTR needs no padding, so this stays 45 bytes."] {
+			let (cipher_text, plain_text) = round_trip(message).unwrap();
+
+			assert_eq!(plain_text, message);
+			assert_eq!(cipher_text.len(), BLOCK_SIZE / 2 + message.len());
+		}
+	}
+}
@@ -0,0 +1,42 @@
+//! Encrypts and decrypts a message with ECB, and shows the repeated blocks that make it
+//! insecure.
+//!
+//! ```text
+//! cargo run --example ecb
+//! ```
+
+use aes_modes::{ecb_decrypt, ecb_encrypt, hex_encode, repeated_block_count, DecryptError, BLOCK_SIZE};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+fn main() {
+	// The same 16 bytes twice, so the ciphertext has the same block twice.
+	let message = b"SIXTEEN BYTES!!!SIXTEEN BYTES!!! and then some";
+	let (cipher_text, plain_text) = round_trip(message).expect("ecb_encrypt output always decrypts");
+
+	println!("plaintext:  {}", hex_encode(message));
+	println!("ciphertext: {}", hex_encode(&cipher_text));
+	println!("repeated ciphertext blocks: {}", repeated_block_count(&cipher_text));
+	println!("decrypted:  {:?}", String::from_utf8_lossy(&plain_text));
+}
+
+/// Encrypts `message` and decrypts the result, returning both.
+fn round_trip(message: &[u8]) -> Result<(Vec<u8>, Vec<u8>), DecryptError> {
+	let cipher_text = ecb_encrypt(message.to_vec(), KEY);
+	let plain_text = ecb_decrypt(cipher_text.clone(), KEY)?;
+
+	Ok((cipher_text, plain_text))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn repeated_plaintext_blocks_show_through() {
+		let (cipher_text, plain_text) = round_trip(b"SIXTEEN BYTES!!!SIXTEEN BYTES!!!").unwrap();
+
+		assert_eq!(plain_text, b"SIXTEEN BYTES!!!SIXTEEN BYTES!!!");
+		assert_eq!(repeated_block_count(&cipher_text), 1);
+	}
+}
@@ -0,0 +1,103 @@
+//! A padding oracle attack on CBC: decrypting a ciphertext without the key, given only a way
+//! to ask whether a ciphertext decrypts to valid padding.
+//!
+//! ```text
+//! cargo run --example padding_oracle --features demo
+//! ```
+//!
+//! CBC decrypts block i as P_i = D(K, C_i) xor C_{i-1}. The attacker can't compute D(K, C_i),
+//! but controls C_{i-1}. Sending `C' || C_i` with a chosen C' makes the last plaintext byte
+//! D(K, C_i)[15] xor C'[15]. Only one of the 256 values of C'[15] makes that byte 0x01, valid
+//! padding. The oracle says which, and that gives D(K, C_i)[15]. Then the last byte is set to
+//! decrypt to 0x02 and the second to last is searched until the padding is `02 02`, and so
+//! on through the block. XORing D(K, C_i) with the real C_{i-1} gives the plaintext.
+//!
+//! This takes at most 256 queries per byte. The fix is to authenticate the ciphertext (as
+//! OCB and GCM do) so a modified one is rejected before its padding is ever looked at.
+
+use aes_modes::{cbc_encrypt, hex_encode, padding_oracle, un_pad, BLOCK_SIZE};
+
+fn main() {
+	let key: [u8; BLOCK_SIZE] = rand::random();
+	let cipher_text = cbc_encrypt(b"Attack at dawn. Bring the padding oracle.".to_vec(), key);
+	println!("ciphertext: {}", hex_encode(&cipher_text));
+
+	// The attacker only ever gets to call the oracle, never to see the key.
+	let mut queries = 0;
+	let plain_text = recover_plain_text(&cipher_text, |query| {
+		queries += 1;
+		padding_oracle(query, key)
+	});
+
+	println!("recovered:  {:?}, with {queries} oracle queries", String::from_utf8_lossy(&plain_text));
+}
+
+/// Decrypts a cbc_encrypt ciphertext, IV first, using nothing but `oracle`, which says
+/// whether a ciphertext decrypts to valid padding.
+fn recover_plain_text(cipher_text: &[u8], mut oracle: impl FnMut(&[u8]) -> bool) -> Vec<u8> {
+	let blocks: Vec<[u8; BLOCK_SIZE]> =
+		cipher_text.chunks_exact(BLOCK_SIZE).map(|block| block.try_into().expect("a whole block")).collect();
+
+	let mut padded = Vec::with_capacity(cipher_text.len());
+	for pair in blocks.windows(2) {
+		let decrypted = recover_block_decryption(pair[1], &mut oracle);
+		padded.extend(decrypted.iter().zip(pair[0]).map(|(d, previous)| d ^ previous));
+	}
+
+	un_pad(padded).expect("the recovered plaintext ends in the original padding")
+}
+
+/// Finds D(K, block), what the block decrypts to before the XOR with the block before it,
+/// one byte at a time from the end.
+fn recover_block_decryption(block: [u8; BLOCK_SIZE], oracle: &mut impl FnMut(&[u8]) -> bool) -> [u8; BLOCK_SIZE] {
+	let mut decrypted = [0u8; BLOCK_SIZE];
+	// A chosen previous block followed by the block under attack.
+	let mut query = [0u8; 2 * BLOCK_SIZE];
+	query[BLOCK_SIZE..].copy_from_slice(&block);
+
+	for position in (0..BLOCK_SIZE).rev() {
+		let pad = (BLOCK_SIZE - position) as u8;
+		// Make every byte found so far decrypt to the padding value being aimed for.
+		for i in position + 1..BLOCK_SIZE {
+			query[i] = decrypted[i] ^ pad;
+		}
+
+		let guess = (0..=255)
+			.find(|&guess| {
+				query[position] = guess;
+				if !oracle(&query) {
+					return false;
+				}
+				// For the last byte, `02 02` (or longer) would also be valid padding if the byte
+				// before happened to decrypt to 0x02. Changing that byte rules it out.
+				if position == BLOCK_SIZE - 1 {
+					query[position - 1] ^= 1;
+					let still_valid = oracle(&query);
+					query[position - 1] ^= 1;
+					return still_valid;
+				}
+				true
+			})
+			.expect("exactly one guess gives valid padding");
+
+		decrypted[position] = guess ^ pad;
+	}
+
+	decrypted
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn the_attack_recovers_the_plaintext_without_the_key() {
+		let key = *b"YELLOW SUBMARINE";
+		let messages: [&[u8]; 3] = [b"", b"exactly sixteen!", b"Attack at dawn. Bring the padding oracle."];
+
+		for message in messages {
+			let cipher_text = cbc_encrypt(message.to_vec(), key);
+			assert_eq!(recover_plain_text(&cipher_text, |query| padding_oracle(query, key)), message);
+		}
+	}
+}
@@ -0,0 +1,76 @@
+//! The three classic modes behind one trait, for code that wants to treat them uniformly.
+//!
+//! The impls call the free functions, rather than the free functions calling the trait.
+//! Each mode has more entry points than the trait's two: CBC alone has a caller-supplied IV,
+//! a detached IV, bounded, uniform-error and zeroizing decrypts and a streaming encrypt.
+//! Those share private cores such as cbc_encrypt_blocks, generic over the key type, and the
+//! trait only covers the common case. Keeping the code in the cores lets every variant
+//! reach it the same way, with the trait as one more thin caller.
+//!
+//! ```
+//! use aes_modes::{BlockMode, Cbc, Ctr, Ecb};
+//!
+//! let key = *b"YELLOW SUBMARINE";
+//! let modes: [&dyn BlockMode; 3] = [&Ecb, &Cbc, &Ctr];
+//!
+//! for mode in modes {
+//!     let cipher_text = mode.encrypt(b"hello world".to_vec(), key);
+//!     assert_eq!(mode.decrypt(cipher_text, key).unwrap(), b"hello world");
+//! }
+//! ```
+
+use crate::{cbc_decrypt, cbc_encrypt, ctr_decrypt, ctr_encrypt, ecb_decrypt, ecb_encrypt, AesKey, DecryptError};
+
+/// A block cipher mode of operation: a way of encrypting messages of any length with AES.
+///
+/// `K` is the key type and defaults to an AES-128 key, so `&dyn BlockMode` works as is. Each
+/// impl just calls the matching free functions, which stay the main API.
+pub trait BlockMode<K: AesKey = [u8; 16]> {
+	/// Encrypts `plain_text`, with a fresh random IV or nonce for the modes that use one.
+	fn encrypt(&self, plain_text: Vec<u8>, key: K) -> Vec<u8>;
+
+	/// Opposite of encrypt.
+	fn decrypt(&self, cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError>;
+}
+
+/// ECB mode: ecb_encrypt and ecb_decrypt. Not secure, see the crate docs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ecb;
+
+/// CBC mode: cbc_encrypt and cbc_decrypt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cbc;
+
+/// CTR mode: ctr_encrypt and ctr_decrypt, with the default counter layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ctr;
+
+impl<K: AesKey> BlockMode<K> for Ecb {
+	fn encrypt(&self, plain_text: Vec<u8>, key: K) -> Vec<u8> {
+		ecb_encrypt(plain_text, key)
+	}
+
+	fn decrypt(&self, cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+		ecb_decrypt(cipher_text, key)
+	}
+}
+
+impl<K: AesKey> BlockMode<K> for Cbc {
+	fn encrypt(&self, plain_text: Vec<u8>, key: K) -> Vec<u8> {
+		cbc_encrypt(plain_text, key)
+	}
+
+	fn decrypt(&self, cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+		cbc_decrypt(cipher_text, key)
+	}
+}
+
+impl<K: AesKey> BlockMode<K> for Ctr {
+	fn encrypt(&self, plain_text: Vec<u8>, key: K) -> Vec<u8> {
+		ctr_encrypt(plain_text, key)
+	}
+
+	fn decrypt(&self, cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+		ctr_decrypt(cipher_text, key)
+	}
+}
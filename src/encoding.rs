@@ -0,0 +1,215 @@
+//! String wrappers around the mode functions, for printing or storing ciphertext as text.
+//!
+//! The key is given as 32, 48 or 64 hex characters, for AES-128, AES-192 or AES-256, the same
+//! as on the command line. Encryption takes a `&str` plaintext and
+//! returns the ciphertext hex (or base64) encoded. Decryption reverses that, and fails if the
+//! plaintext isn't valid UTF-8. The byte-oriented functions underneath are unchanged.
+
+use crate::{BlockMode, Cbc, Ctr, DecryptError, Ecb};
+
+/// Why a string wrapper failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodingError {
+	/// A hex string had an odd length or a character that isn't a hex digit.
+	InvalidHex,
+	/// A base64 string could not be decoded.
+	InvalidBase64,
+	/// The key did not decode to 16, 24 or 32 bytes.
+	InvalidKeyLength,
+	/// The decrypted plaintext is not valid UTF-8.
+	InvalidUtf8,
+	/// The ciphertext decoded fine but could not be decrypted.
+	Decrypt(DecryptError),
+}
+
+impl std::fmt::Display for EncodingError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			EncodingError::InvalidHex => write!(f, "not a valid hex string"),
+			EncodingError::InvalidBase64 => write!(f, "not a valid base64 string"),
+			EncodingError::InvalidKeyLength => write!(f, "key must be 16, 24 or 32 bytes (32, 48 or 64 hex characters)"),
+			EncodingError::InvalidUtf8 => write!(f, "plaintext is not valid UTF-8"),
+			EncodingError::Decrypt(e) => e.fmt(f),
+		}
+	}
+}
+
+impl std::error::Error for EncodingError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			EncodingError::Decrypt(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<DecryptError> for EncodingError {
+	fn from(e: DecryptError) -> Self {
+		EncodingError::Decrypt(e)
+	}
+}
+
+/// Lowercase hex encoding of `bytes`.
+pub fn hex_encode(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes a hex string, in either case. Returns `InvalidHex` for an odd length or any
+/// character that isn't a hex digit.
+pub fn hex_decode(hex: &str) -> Result<Vec<u8>, EncodingError> {
+	// Checked up front: from_str_radix would also accept a leading '+'.
+	if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+		return Err(EncodingError::InvalidHex);
+	}
+
+	Ok((0..hex.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("checked for hex digits"))
+		.collect())
+}
+
+/// ecb_encrypt of `plain_text`, returned as hex.
+pub fn ecb_encrypt_hex(plain_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	encrypt_with(plain_text, key_hex, Ecb, hex_encode)
+}
+
+/// ecb_decrypt of hex produced by ecb_encrypt_hex.
+pub fn ecb_decrypt_hex(cipher_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	decrypt_with(cipher_text, key_hex, Ecb, hex_decode)
+}
+
+/// cbc_encrypt of `plain_text`, returned as hex.
+pub fn cbc_encrypt_hex(plain_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	encrypt_with(plain_text, key_hex, Cbc, hex_encode)
+}
+
+/// cbc_decrypt of hex produced by cbc_encrypt_hex.
+pub fn cbc_decrypt_hex(cipher_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	decrypt_with(cipher_text, key_hex, Cbc, hex_decode)
+}
+
+/// ctr_encrypt of `plain_text`, returned as hex.
+pub fn ctr_encrypt_hex(plain_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	encrypt_with(plain_text, key_hex, Ctr, hex_encode)
+}
+
+/// ctr_decrypt of hex produced by ctr_encrypt_hex.
+pub fn ctr_decrypt_hex(cipher_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	decrypt_with(cipher_text, key_hex, Ctr, hex_decode)
+}
+
+/// ecb_encrypt of `plain_text`, returned as base64.
+#[cfg(feature = "base64")]
+pub fn ecb_encrypt_base64(plain_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	encrypt_with(plain_text, key_hex, Ecb, base64_encode)
+}
+
+/// ecb_decrypt of base64 produced by ecb_encrypt_base64.
+#[cfg(feature = "base64")]
+pub fn ecb_decrypt_base64(cipher_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	decrypt_with(cipher_text, key_hex, Ecb, base64_decode)
+}
+
+/// cbc_encrypt of `plain_text`, returned as base64.
+#[cfg(feature = "base64")]
+pub fn cbc_encrypt_base64(plain_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	encrypt_with(plain_text, key_hex, Cbc, base64_encode)
+}
+
+/// cbc_decrypt of base64 produced by cbc_encrypt_base64.
+#[cfg(feature = "base64")]
+pub fn cbc_decrypt_base64(cipher_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	decrypt_with(cipher_text, key_hex, Cbc, base64_decode)
+}
+
+/// ctr_encrypt of `plain_text`, returned as base64.
+#[cfg(feature = "base64")]
+pub fn ctr_encrypt_base64(plain_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	encrypt_with(plain_text, key_hex, Ctr, base64_encode)
+}
+
+/// ctr_decrypt of base64 produced by ctr_encrypt_base64.
+#[cfg(feature = "base64")]
+pub fn ctr_decrypt_base64(cipher_text: &str, key_hex: &str) -> Result<String, EncodingError> {
+	decrypt_with(cipher_text, key_hex, Ctr, base64_decode)
+}
+
+/// Standard base64, with padding.
+#[cfg(feature = "base64")]
+pub fn base64_encode(bytes: &[u8]) -> String {
+	use base64::Engine;
+
+	base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Decodes standard base64, with padding. Returns `InvalidBase64` for anything else.
+#[cfg(feature = "base64")]
+pub fn base64_decode(text: &str) -> Result<Vec<u8>, EncodingError> {
+	use base64::Engine;
+
+	base64::engine::general_purpose::STANDARD.decode(text).map_err(|_| EncodingError::InvalidBase64)
+}
+
+/// Decodes a base64 key of the size `K` asks for, e.g. `key_from_base64::<[u8; 16]>(text)`
+/// for AES-128 or `key_from_base64::<[u8; 32]>(text)` for AES-256. Returns
+/// `InvalidKeyLength` if it decodes to any other number of bytes.
+#[cfg(feature = "base64")]
+pub fn key_from_base64<K: crate::AesKey + TryFrom<Vec<u8>>>(text: &str) -> Result<K, EncodingError> {
+	K::try_from(base64_decode(text)?).map_err(|_| EncodingError::InvalidKeyLength)
+}
+
+/// A decoded key, of whichever size it turned out to be.
+enum Key {
+	Aes128([u8; 16]),
+	Aes192([u8; 24]),
+	Aes256([u8; 32]),
+}
+
+fn parse_key(key_hex: &str) -> Result<Key, EncodingError> {
+	let key = hex_decode(key_hex)?;
+
+	match key.len() {
+		16 => Ok(Key::Aes128(key.try_into().expect("checked the length"))),
+		24 => Ok(Key::Aes192(key.try_into().expect("checked the length"))),
+		32 => Ok(Key::Aes256(key.try_into().expect("checked the length"))),
+		_ => Err(EncodingError::InvalidKeyLength),
+	}
+}
+
+/// A mode the wrappers can run with a key of any size.
+trait AnyKeyMode: BlockMode<[u8; 16]> + BlockMode<[u8; 24]> + BlockMode<[u8; 32]> {}
+
+impl<M: BlockMode<[u8; 16]> + BlockMode<[u8; 24]> + BlockMode<[u8; 32]>> AnyKeyMode for M {}
+
+fn encrypt_with(
+	plain_text: &str,
+	key_hex: &str,
+	mode: impl AnyKeyMode,
+	encode: fn(&[u8]) -> String,
+) -> Result<String, EncodingError> {
+	let plain_text = plain_text.as_bytes().to_vec();
+	let cipher_text = match parse_key(key_hex)? {
+		Key::Aes128(key) => mode.encrypt(plain_text, key),
+		Key::Aes192(key) => mode.encrypt(plain_text, key),
+		Key::Aes256(key) => mode.encrypt(plain_text, key),
+	};
+
+	Ok(encode(&cipher_text))
+}
+
+fn decrypt_with(
+	cipher_text: &str,
+	key_hex: &str,
+	mode: impl AnyKeyMode,
+	decode: fn(&str) -> Result<Vec<u8>, EncodingError>,
+) -> Result<String, EncodingError> {
+	let key = parse_key(key_hex)?;
+	let cipher_text = decode(cipher_text)?;
+	let plain_text = match key {
+		Key::Aes128(key) => mode.decrypt(cipher_text, key),
+		Key::Aes192(key) => mode.decrypt(cipher_text, key),
+		Key::Aes256(key) => mode.decrypt(cipher_text, key),
+	}?;
+
+	String::from_utf8(plain_text).map_err(|_| EncodingError::InvalidUtf8)
+}
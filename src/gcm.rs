@@ -0,0 +1,518 @@
+//! AES-GCM authenticated encryption, as specified in NIST SP 800-38D.
+//!
+//! GCM is CTR mode plus an authentication tag. The counter block starts from the 96-bit
+//! nonce followed by a 32-bit big-endian counter, J0 = `nonce || 1`. Block i of the plaintext
+//! is XORed with E(K, J0 + i), so like CTR it needs no padding. The tag is GHASH, a
+//! polynomial hash keyed by H = E(K, 0^128), taken over the associated data and the
+//! ciphertext, then XORed with E(K, J0).
+//!
+//! Like CBC and CTR, the nonce is generated randomly and prepended, so the output is
+//! `nonce || ciphertext || tag`. The nonce must never repeat under the same key: a repeat
+//! reveals the XOR of the plaintexts and lets an attacker forge tags.
+//!
+//! SP 800-38D also allows IVs of any other length, for protocols that have one already. Those
+//! don't fit the counter block, so J0 is derived by GHASHing the IV instead:
+//! J0 = GHASH_H(IV || 0-padding || 0^64 || len(IV)), and counting goes on from there.
+//! gcm_encrypt_with_iv and gcm_decrypt_with_iv take such IVs. The 96-bit case is kept
+//! separate because that derivation would give a different J0 for it.
+
+use aes::cipher::{consts::U16, BlockEncrypt};
+
+use crate::{aes_encrypt_block, ct_ciphertext_eq, encode_aad_list, random_iv, AesKey, DecryptError, BLOCK_SIZE};
+
+/// The length of a GCM nonce. SP 800-38D allows other lengths (see gcm_encrypt_with_iv),
+/// but 96 bits is the one it recommends, and the only one that maps directly onto the
+/// counter block.
+pub const GCM_NONCE_SIZE: usize = 12;
+
+/// The length of the authentication tag appended to the ciphertext.
+pub const GCM_TAG_SIZE: usize = 16;
+
+/// The shortest tag gcm_encrypt_with_tag_len accepts. SP 800-38D allows 12 to 16 bytes in
+/// general, and 8 or 4 only under the extra limits of its appendix C.
+pub const GCM_MIN_TAG_SIZE: usize = 4;
+
+/// The longest plaintext one nonce can encrypt: the 32-bit counter has 2^32 - 2 blocks
+/// left after J0 and the first counter value.
+const GCM_MAX_LEN: u64 = ((1 << 32) - 2) * BLOCK_SIZE as u64;
+
+/// Encrypts and authenticates `plain_text`, and authenticates (but doesn't encrypt)
+/// `associated_data`, under a random nonce.
+///
+/// # Panics
+///
+/// If `plain_text` is longer than GCM allows under one nonce, about 64 GiB.
+pub fn gcm_encrypt<K: AesKey>(plain_text: Vec<u8>, key: K, associated_data: &[u8]) -> Vec<u8> {
+	gcm_encrypt_with_nonce(plain_text, key, associated_data, random_iv())
+}
+
+/// Same as gcm_encrypt, but with the nonce supplied by the caller. It is still prepended to
+/// the ciphertext.
+///
+/// This is meant for reproducing known test vectors. Reusing a nonce under the same key
+/// breaks both the confidentiality and the integrity of GCM, so anything else should use
+/// gcm_encrypt and let it pick a random one.
+pub fn gcm_encrypt_with_nonce<K: AesKey>(
+	plain_text: Vec<u8>,
+	key: K,
+	associated_data: &[u8],
+	nonce: [u8; GCM_NONCE_SIZE],
+) -> Vec<u8> {
+	gcm_seal(&nonce, plain_text, key, associated_data, &nonce, GCM_TAG_SIZE)
+}
+
+/// Same as gcm_encrypt, but with the tag cut down to its first `tag_len` bytes, as some
+/// protocols do to save space. Decrypt with gcm_decrypt_with_tag_len and the same `tag_len`.
+///
+/// A forgery only has to guess the shorter tag, so it succeeds with probability 2^-(8 *
+/// tag_len) per attempt: one in 2^32 for a 4-byte tag, which an attacker who can submit
+/// ciphertexts freely will eventually hit. SP 800-38D also shows that each failed forgery
+/// against a short tag reveals something about H, making later ones easier. Use the full 16
+/// bytes unless the protocol requires less, and below 12 bytes limit how many messages and
+/// failed decryptions a key may see, as its appendix C describes.
+///
+/// # Panics
+///
+/// If `tag_len` is not between GCM_MIN_TAG_SIZE and GCM_TAG_SIZE, or `plain_text` is
+/// longer than GCM allows.
+pub fn gcm_encrypt_with_tag_len<K: AesKey>(
+	plain_text: Vec<u8>,
+	key: K,
+	associated_data: &[u8],
+	tag_len: usize,
+) -> Vec<u8> {
+	let nonce: [u8; GCM_NONCE_SIZE] = random_iv();
+	gcm_seal(&nonce, plain_text, key, associated_data, &nonce, tag_len)
+}
+
+/// Same as gcm_encrypt, but authenticates a list of associated data fields, encoded with
+/// encode_aad_list so that their boundaries are authenticated too. Decrypt with
+/// gcm_decrypt_with_aad_list and the same list.
+pub fn gcm_encrypt_with_aad_list<K: AesKey>(plain_text: Vec<u8>, key: K, associated_data: &[&[u8]]) -> Vec<u8> {
+	gcm_encrypt(plain_text, key, &encode_aad_list(associated_data))
+}
+
+/// Same as gcm_encrypt_with_nonce, but for formats that store the nonce and the tag in
+/// fields of their own: the ciphertext comes back with neither attached, and the tag
+/// separately. Decrypt with gcm_decrypt_detached.
+///
+/// The output is the same as gcm_encrypt_with_nonce's, rearranged, so either form can be
+/// turned into the other. As there, the nonce must never repeat under the same key.
+///
+/// # Panics
+///
+/// If `plain_text` is longer than GCM allows under one nonce.
+pub fn gcm_encrypt_detached<K: AesKey>(
+	plain_text: Vec<u8>,
+	key: K,
+	associated_data: &[u8],
+	nonce: [u8; GCM_NONCE_SIZE],
+) -> (Vec<u8>, [u8; GCM_TAG_SIZE]) {
+	let mut cipher_text = gcm_seal(&[], plain_text, key, associated_data, &nonce, GCM_TAG_SIZE);
+
+	let body_len = cipher_text.len() - GCM_TAG_SIZE;
+	let tag = cipher_text[body_len..].try_into().expect("the tag is at the end");
+	cipher_text.truncate(body_len);
+
+	(cipher_text, tag)
+}
+
+/// Same as gcm_encrypt_with_nonce, but for an IV of any non-zero length, such as one taken
+/// from another protocol's nonce or counter. The IV is not prepended, since its length
+/// varies, so the output is just `ciphertext || tag` and the caller keeps track of the IV.
+///
+/// Any length other than GCM_NONCE_SIZE goes through the GHASH derivation of J0 described
+/// in the module docs. The same rule as for nonces applies: never reuse an IV under a key.
+///
+/// # Panics
+///
+/// If `iv` is empty, or `plain_text` is longer than GCM allows.
+pub fn gcm_encrypt_with_iv<K: AesKey>(plain_text: Vec<u8>, key: K, associated_data: &[u8], iv: &[u8]) -> Vec<u8> {
+	gcm_seal(&[], plain_text, key, associated_data, iv, GCM_TAG_SIZE)
+}
+
+/// The GCM encryption that the public variants share. The output is `header`, then the
+/// ciphertext, then the tag.
+fn gcm_seal<K: AesKey>(
+	header: &[u8],
+	plain_text: Vec<u8>,
+	key: K,
+	associated_data: &[u8],
+	iv: &[u8],
+	tag_len: usize,
+) -> Vec<u8> {
+	mode_span!("gcm_encrypt", "gcm", plain_text.len(), plain_text.len().div_ceil(BLOCK_SIZE));
+	check_tag_len(tag_len);
+	assert!(!iv.is_empty(), "GCM IVs can't be empty");
+	assert!(plain_text.len() as u64 <= GCM_MAX_LEN, "plaintext too long for one GCM nonce");
+
+	let gcm = Gcm::new(key, iv);
+	let mut cipher_text = Vec::with_capacity(header.len() + plain_text.len() + tag_len);
+	cipher_text.extend_from_slice(header);
+	cipher_text.extend(gcm.apply_keystream(&plain_text));
+
+	let tag = gcm.tag(associated_data, &cipher_text[header.len()..]);
+	cipher_text.extend_from_slice(&tag[..tag_len]);
+
+	cipher_text
+}
+
+/// Opposite of gcm_encrypt. `associated_data` must be the same as when encrypting.
+///
+/// Returns `AuthenticationFailed` if the tag doesn't verify, which means the ciphertext,
+/// the associated data or the key is not what was used to encrypt. The tag is checked
+/// before anything is decrypted, so no plaintext is released in that case.
+pub fn gcm_decrypt<K: AesKey>(cipher_text: Vec<u8>, key: K, associated_data: &[u8]) -> Result<Vec<u8>, DecryptError> {
+	gcm_decrypt_with_tag_len(cipher_text, key, associated_data, GCM_TAG_SIZE)
+}
+
+/// Opposite of gcm_encrypt_with_tag_len. Only the `tag_len` bytes of tag at the end of
+/// `cipher_text` are checked, so see gcm_encrypt_with_tag_len for what short tags cost.
+///
+/// # Panics
+///
+/// If `tag_len` is not between GCM_MIN_TAG_SIZE and GCM_TAG_SIZE.
+pub fn gcm_decrypt_with_tag_len<K: AesKey>(
+	cipher_text: Vec<u8>,
+	key: K,
+	associated_data: &[u8],
+	tag_len: usize,
+) -> Result<Vec<u8>, DecryptError> {
+	mode_span!(
+		"gcm_decrypt",
+		"gcm",
+		cipher_text.len(),
+		cipher_text.len().saturating_sub(GCM_NONCE_SIZE + tag_len).div_ceil(BLOCK_SIZE)
+	);
+	check_tag_len(tag_len);
+
+	if cipher_text.len() < GCM_NONCE_SIZE {
+		return Err(DecryptError::InvalidLength);
+	}
+	let (nonce, rest) = cipher_text.split_at(GCM_NONCE_SIZE);
+
+	gcm_open(rest, key, associated_data, nonce, tag_len)
+}
+
+/// Opposite of gcm_encrypt_detached: decrypts `cipher_text`, which has no nonce or tag
+/// attached, after checking it against `tag`.
+pub fn gcm_decrypt_detached<K: AesKey>(
+	cipher_text: Vec<u8>,
+	key: K,
+	associated_data: &[u8],
+	nonce: [u8; GCM_NONCE_SIZE],
+	tag: &[u8; GCM_TAG_SIZE],
+) -> Result<Vec<u8>, DecryptError> {
+	mode_span!("gcm_decrypt", "gcm", cipher_text.len(), cipher_text.len().div_ceil(BLOCK_SIZE));
+
+	let gcm = gcm_check_tag(&cipher_text, tag, key, associated_data, &nonce)?;
+	Ok(gcm.apply_keystream(&cipher_text))
+}
+
+/// Opposite of gcm_encrypt_with_iv. `cipher_text` is `ciphertext || tag`, and `iv` the same
+/// IV it was encrypted with.
+pub fn gcm_decrypt_with_iv<K: AesKey>(
+	cipher_text: Vec<u8>,
+	key: K,
+	associated_data: &[u8],
+	iv: &[u8],
+) -> Result<Vec<u8>, DecryptError> {
+	mode_span!(
+		"gcm_decrypt",
+		"gcm",
+		cipher_text.len(),
+		cipher_text.len().saturating_sub(GCM_TAG_SIZE).div_ceil(BLOCK_SIZE)
+	);
+	if iv.is_empty() {
+		return Err(DecryptError::InvalidLength);
+	}
+
+	gcm_open(&cipher_text, key, associated_data, iv, GCM_TAG_SIZE)
+}
+
+/// Opposite of gcm_encrypt_with_aad_list. The fields must be the same, and split the same
+/// way, as when encrypting.
+pub fn gcm_decrypt_with_aad_list<K: AesKey>(
+	cipher_text: Vec<u8>,
+	key: K,
+	associated_data: &[&[u8]],
+) -> Result<Vec<u8>, DecryptError> {
+	gcm_decrypt(cipher_text, key, &encode_aad_list(associated_data))
+}
+
+/// Checks that `cipher_text` is authentic without decrypting it, for callers that only need
+/// to know whether to accept a message, e.g. before routing it on. `cipher_text` is the
+/// output of gcm_encrypt and `associated_data` must be the same as when encrypting.
+///
+/// Only GHASH runs, over data that is already there, so no plaintext buffer is allocated
+/// and no keystream is generated. Returns `Ok(())` exactly when gcm_decrypt would succeed.
+pub fn gcm_verify<K: AesKey>(cipher_text: &[u8], key: K, associated_data: &[u8]) -> Result<(), DecryptError> {
+	mode_span!(
+		"gcm_verify",
+		"gcm",
+		cipher_text.len(),
+		cipher_text.len().saturating_sub(GCM_NONCE_SIZE + GCM_TAG_SIZE).div_ceil(BLOCK_SIZE)
+	);
+	if cipher_text.len() < GCM_NONCE_SIZE {
+		return Err(DecryptError::InvalidLength);
+	}
+	let (nonce, rest) = cipher_text.split_at(GCM_NONCE_SIZE);
+
+	gcm_authenticate(rest, key, associated_data, nonce, GCM_TAG_SIZE).map(|_| ())
+}
+
+/// The GCM decryption that the public variants share, of `ciphertext || tag` with the
+/// nonce or IV already split off.
+fn gcm_open<K: AesKey>(
+	cipher_text: &[u8],
+	key: K,
+	associated_data: &[u8],
+	iv: &[u8],
+	tag_len: usize,
+) -> Result<Vec<u8>, DecryptError> {
+	let (gcm, body) = gcm_authenticate(cipher_text, key, associated_data, iv, tag_len)?;
+
+	Ok(gcm.apply_keystream(body))
+}
+
+/// Verifies the tag at the end of `ciphertext || tag`, and returns the ciphertext part with
+/// the key and IV setup that decrypts it.
+fn gcm_authenticate<'a, K: AesKey>(
+	cipher_text: &'a [u8],
+	key: K,
+	associated_data: &[u8],
+	iv: &[u8],
+	tag_len: usize,
+) -> Result<(Gcm<K::Cipher>, &'a [u8]), DecryptError> {
+	if cipher_text.len() < tag_len {
+		return Err(DecryptError::InvalidLength);
+	}
+	let (body, tag) = cipher_text.split_at(cipher_text.len() - tag_len);
+
+	Ok((gcm_check_tag(body, tag, key, associated_data, iv)?, body))
+}
+
+/// Verifies `tag` for the ciphertext `body`, and returns the key and IV setup that decrypts it.
+fn gcm_check_tag<K: AesKey>(
+	body: &[u8],
+	tag: &[u8],
+	key: K,
+	associated_data: &[u8],
+	iv: &[u8],
+) -> Result<Gcm<K::Cipher>, DecryptError> {
+	if body.len() as u64 > GCM_MAX_LEN {
+		return Err(DecryptError::InvalidLength);
+	}
+
+	let gcm = Gcm::new(key, iv);
+	if !ct_ciphertext_eq(&gcm.tag(associated_data, body)[..tag.len()], tag) {
+		return Err(DecryptError::AuthenticationFailed);
+	}
+
+	Ok(gcm)
+}
+
+fn check_tag_len(tag_len: usize) {
+	assert!(
+		(GCM_MIN_TAG_SIZE..=GCM_TAG_SIZE).contains(&tag_len),
+		"GCM tags must be {GCM_MIN_TAG_SIZE} to {GCM_TAG_SIZE} bytes, not {tag_len}"
+	);
+}
+
+/// The expanded key with the two values derived from it and the nonce.
+struct Gcm<C> {
+	cipher: C,
+	/// The GHASH key, H = E(K, 0^128).
+	h: GhashKey,
+	/// The pre-counter block J0: `nonce || 1` for a 96-bit nonce, otherwise derived from the
+	/// IV with GHASH.
+	j0: [u8; BLOCK_SIZE],
+}
+
+impl<C: BlockEncrypt<BlockSize = U16>> Gcm<C> {
+	fn new<K: AesKey<Cipher = C>>(key: K, iv: &[u8]) -> Self {
+		let cipher = key.cipher();
+		let h = GhashKey::new(u128::from_be_bytes(aes_encrypt_block(&cipher, [0; BLOCK_SIZE])));
+
+		let j0 = if iv.len() == GCM_NONCE_SIZE {
+			let mut j0 = [0u8; BLOCK_SIZE];
+			j0[..GCM_NONCE_SIZE].copy_from_slice(iv);
+			j0[BLOCK_SIZE - 1] = 1;
+			j0
+		} else {
+			// GHASH of the zero-padded IV and then a block holding 0^64 || len(IV), which is just
+			// what ghash computes with no associated data and the IV in place of the ciphertext.
+			ghash(&h, &[], iv).to_be_bytes()
+		};
+
+		Gcm { cipher, h, j0 }
+	}
+
+	/// XORs `data` with the keystream E(K, J0 + 1), E(K, J0 + 2), ..., truncating the last
+	/// keystream block to the data. Encryption and decryption are the same operation.
+	fn apply_keystream(&self, data: &[u8]) -> Vec<u8> {
+		let mut counter_block = self.j0;
+		let mut output = Vec::with_capacity(data.len());
+
+		for chunk in data.chunks(BLOCK_SIZE) {
+			counter_block = inc32(counter_block);
+			let keystream = aes_encrypt_block(&self.cipher, counter_block);
+			output.extend(chunk.iter().zip(keystream).map(|(d, k)| d ^ k));
+		}
+
+		output
+	}
+
+	/// T = GHASH_H(A, C) xor E(K, J0).
+	fn tag(&self, associated_data: &[u8], cipher_text: &[u8]) -> [u8; GCM_TAG_SIZE] {
+		(ghash(&self.h, associated_data, cipher_text) ^ u128::from_be_bytes(aes_encrypt_block(&self.cipher, self.j0)))
+			.to_be_bytes()
+	}
+}
+
+/// GHASH_H over A and C, each zero-padded to whole blocks, and then a final block holding
+/// the bit lengths of A and C, 64 bits each.
+fn ghash(h: &GhashKey, associated_data: &[u8], cipher_text: &[u8]) -> u128 {
+	let mut hash = 0u128;
+	for block in associated_data.chunks(BLOCK_SIZE).chain(cipher_text.chunks(BLOCK_SIZE)) {
+		hash = h.mul(hash ^ zero_padded(block));
+	}
+
+	let lengths = (u128::from(associated_data.len() as u64 * 8) << 64) | u128::from(cipher_text.len() as u64 * 8);
+	h.mul(hash ^ lengths)
+}
+
+/// H, ready for GHASH to multiply by. By default that is gf_mul, with no precomputation.
+#[cfg(not(feature = "table-ghash"))]
+struct GhashKey(u128);
+
+#[cfg(not(feature = "table-ghash"))]
+impl GhashKey {
+	fn new(h: u128) -> Self {
+		GhashKey(h)
+	}
+
+	fn mul(&self, x: u128) -> u128 {
+		gf_mul(x, self.0)
+	}
+}
+
+/// H, ready for GHASH to multiply by, as 4-bit tables: entry `[p][n]` is n times H, with the
+/// nibble n standing in for bits 4p to 4p + 3 of the other factor. A multiplication is then
+/// 32 lookups and XORs instead of gf_mul's 128 rounds of shifting and masking. In the
+/// `ghash` benchmark that takes gcm_verify over 64 KB from about 45 MiB/s to 350 MiB/s.
+///
+/// The catch is that the lookups are indexed by the GHASH state, which depends on H and the
+/// data. Which table entries got loaded shows up in the CPU cache, so an attacker who shares
+/// the machine and can time their own memory accesses may learn bits of H, and with H they
+/// can forge tags. That is why this is behind the `table-ghash` feature and gf_mul stays the
+/// default. The tables take 8 KiB and are rebuilt for every message.
+#[cfg(feature = "table-ghash")]
+struct GhashKey([[u128; 16]; BLOCK_SIZE * 2]);
+
+#[cfg(feature = "table-ghash")]
+impl GhashKey {
+	fn new(h: u128) -> Self {
+		// basis[k] = H * x^k. Multiplying by x is the same shift and fold as in gf_mul.
+		let mut basis = [h; 128];
+		for k in 1..128 {
+			let v = basis[k - 1];
+			basis[k] = (v >> 1) ^ if v & 1 == 1 { 0xe1 << 120 } else { 0 };
+		}
+
+		// Multiplication by H is linear, so each entry is the XOR of the basis values for the
+		// bits set in its nibble. The nibble's top bit stands for x^(4p), its bottom for x^(4p + 3).
+		let mut tables = [[0u128; 16]; BLOCK_SIZE * 2];
+		for (p, table) in tables.iter_mut().enumerate() {
+			for n in 1..16 {
+				table[n] = table[n & (n - 1)] ^ basis[4 * p + 3 - n.trailing_zeros() as usize];
+			}
+		}
+
+		GhashKey(tables)
+	}
+
+	fn mul(&self, x: u128) -> u128 {
+		self.0
+			.iter()
+			.enumerate()
+			.fold(0, |z, (p, table)| z ^ table[((x >> (124 - 4 * p)) & 0xf) as usize])
+	}
+}
+
+/// Adds one to the low 32 bits of the counter block, wrapping within those 32 bits.
+fn inc32(mut block: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+	let counter = u32::from_be_bytes(block[BLOCK_SIZE - 4..].try_into().expect("4 bytes")).wrapping_add(1);
+	block[BLOCK_SIZE - 4..].copy_from_slice(&counter.to_be_bytes());
+	block
+}
+
+/// Multiplies two elements of GF(2^128) in GCM's bit order (SP 800-38D algorithm 1).
+#[cfg_attr(feature = "table-ghash", allow(dead_code))]
+///
+/// GCM numbers the bits of a block from the most significant end, so the reduction by
+/// x^128 + x^7 + x^2 + x + 1 shifts right and folds in 0xe1 at the top. Every bit is
+/// processed the same way, with masks instead of branches, so the time taken doesn't depend
+/// on H or the data.
+fn gf_mul(x: u128, y: u128) -> u128 {
+	const R: u128 = 0xe1 << 120;
+	let mut z = 0u128;
+	let mut v = y;
+
+	for i in (0..128).rev() {
+		z ^= v & 0u128.wrapping_sub((x >> i) & 1);
+		v = (v >> 1) ^ (R & 0u128.wrapping_sub(v & 1));
+	}
+
+	z
+}
+
+/// Reads up to a block of bytes as a big-endian u128, padded with zeros on the right.
+fn zero_padded(bytes: &[u8]) -> u128 {
+	let mut block = [0u8; BLOCK_SIZE];
+	block[..bytes.len()].copy_from_slice(bytes);
+	u128::from_be_bytes(block)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn inc32_wraps_within_the_low_32_bits() {
+		let nonce = [0xab; GCM_NONCE_SIZE];
+		let block = |counter: u32| {
+			let mut block = [0u8; BLOCK_SIZE];
+			block[..GCM_NONCE_SIZE].copy_from_slice(&nonce);
+			block[GCM_NONCE_SIZE..].copy_from_slice(&counter.to_be_bytes());
+			block
+		};
+
+		assert_eq!(inc32(block(1)), block(2));
+		// The carry runs up through the counter bytes, big-endian.
+		assert_eq!(inc32(block(0x0000_00ff)), block(0x0000_0100));
+		// And stops at the top of the counter instead of spilling into the nonce.
+		assert_eq!(inc32(block(u32::MAX)), block(0));
+	}
+
+	#[cfg(feature = "table-ghash")]
+	#[test]
+	fn table_multiplication_matches_gf_mul() {
+		let mut state = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210u128;
+		let mut next = || {
+			// xorshift, just to get a spread of operands with no pattern to them.
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			state
+		};
+
+		for _ in 0..64 {
+			let h = next();
+			let key = GhashKey::new(h);
+			for x in [0, 1, 1 << 127, u128::MAX, next(), next()] {
+				assert_eq!(key.mul(x), gf_mul(x, h), "H = {h:032x}, x = {x:032x}");
+			}
+		}
+	}
+}
@@ -0,0 +1,121 @@
+//! AES Key Wrap, as specified in RFC 3394.
+//!
+//! Key wrap is a special-purpose mode for encrypting other keys under a key-encrypting key
+//! (KEK). It needs no IV or padding, and it detects tampering: unwrapping checks a fixed
+//! integrity value that only comes out right if nothing was modified and the right KEK
+//! was used.
+//!
+//! The key data is handled as n 64-bit halves R[1..n] plus a 64-bit integrity register A.
+//! Six rounds run over all the halves. Each step encrypts `A | R[i]` and then splits the
+//! result: the high half, XORed with a step counter, becomes the new A, and the low half
+//! becomes the new R[i]. Unwrapping runs the same steps backwards with AES decryption.
+
+use crate::{aes_decrypt_block, aes_encrypt_block, concat_arrays, ct_ciphertext_eq, AesKey, DecryptError, BLOCK_SIZE};
+
+/// Key wrap works in 64-bit halves of an AES block.
+const SEMIBLOCK_SIZE: usize = BLOCK_SIZE / 2;
+
+/// The default initial value from RFC 3394 section 2.2.3.1. Unwrapping succeeds only if
+/// this comes back out unchanged.
+const DEFAULT_IV: [u8; SEMIBLOCK_SIZE] = [0xA6; SEMIBLOCK_SIZE];
+
+/// Wraps `key_to_wrap` under the key-encrypting key `kek`, which can be any AES key size. The
+/// output is 8 bytes longer than the input.
+///
+/// # Panics
+///
+/// If `key_to_wrap` is shorter than 16 bytes or not a multiple of 8 bytes. That's a
+/// fixed-size key from the caller, not untrusted input.
+pub fn aes_wrap<K: AesKey>(kek: K, key_to_wrap: &[u8]) -> Vec<u8> {
+	assert!(
+		key_to_wrap.len() >= 2 * SEMIBLOCK_SIZE && key_to_wrap.len().is_multiple_of(SEMIBLOCK_SIZE),
+		"key wrap input must be at least 16 bytes and a multiple of 8 bytes"
+	);
+
+	let cipher = kek.cipher();
+	let mut a = DEFAULT_IV;
+	let mut r: Vec<[u8; SEMIBLOCK_SIZE]> = key_to_wrap.chunks_exact(SEMIBLOCK_SIZE).map(semiblock).collect();
+	let n = r.len();
+
+	for j in 0..6 {
+		for (i, r_i) in r.iter_mut().enumerate() {
+			let b = aes_encrypt_block(&cipher, concat_arrays(a, *r_i));
+			let t = (n * j + i + 1) as u64;
+
+			a = xor_counter(semiblock(&b[..SEMIBLOCK_SIZE]), t);
+			*r_i = semiblock(&b[SEMIBLOCK_SIZE..]);
+		}
+	}
+
+	let mut wrapped = Vec::with_capacity(key_to_wrap.len() + SEMIBLOCK_SIZE);
+	wrapped.extend_from_slice(&a);
+	for half in r {
+		wrapped.extend_from_slice(&half);
+	}
+
+	wrapped
+}
+
+/// Does the opposite of aes_wrap.
+///
+/// Returns `InvalidLength` if `wrapped` could not have come from aes_wrap. Returns
+/// `AuthenticationFailed` if the integrity check fails, meaning the wrapped key was modified
+/// or `kek` is the wrong key. Nothing is returned on failure.
+pub fn aes_unwrap<K: AesKey>(kek: K, wrapped: &[u8]) -> Result<Vec<u8>, DecryptError> {
+	if wrapped.len() < 3 * SEMIBLOCK_SIZE || !wrapped.len().is_multiple_of(SEMIBLOCK_SIZE) {
+		return Err(DecryptError::InvalidLength);
+	}
+
+	let cipher = kek.cipher();
+	let mut a = semiblock(&wrapped[..SEMIBLOCK_SIZE]);
+	let mut r: Vec<[u8; SEMIBLOCK_SIZE]> =
+		wrapped[SEMIBLOCK_SIZE..].chunks_exact(SEMIBLOCK_SIZE).map(semiblock).collect();
+	let n = r.len();
+
+	for j in (0..6).rev() {
+		for i in (0..n).rev() {
+			let t = (n * j + i + 1) as u64;
+			let b = aes_decrypt_block(&cipher, concat_arrays(xor_counter(a, t), r[i]));
+
+			a = semiblock(&b[..SEMIBLOCK_SIZE]);
+			r[i] = semiblock(&b[SEMIBLOCK_SIZE..]);
+		}
+	}
+
+	// Compared without exiting early, so timing doesn't reveal how much of A was right.
+	if !ct_ciphertext_eq(&a, &DEFAULT_IV) {
+		return Err(DecryptError::AuthenticationFailed);
+	}
+
+	Ok(r.concat())
+}
+
+/// Moves a wrapped key from the KEK `old` to the KEK `new`: unwraps it under `old` and
+/// wraps the result under `new`. This is master key rotation for envelope encryption, where
+/// the payload is encrypted under a data key and only the data key is wrapped. The payload
+/// never needs touching, however large it is.
+///
+/// Errors are aes_unwrap's, for `wrapped` under `old`. The unwrapped key is zeroed before
+/// its buffer is freed.
+pub fn rewrap<O: AesKey, N: AesKey>(old: O, new: N, wrapped: &[u8]) -> Result<Vec<u8>, DecryptError> {
+	let mut key = aes_unwrap(old, wrapped)?;
+	let rewrapped = aes_wrap(new, &key);
+
+	// The black_box stops the compiler from removing the writes as dead stores.
+	key.fill(0);
+	std::hint::black_box(&key);
+
+	Ok(rewrapped)
+}
+
+/// Copies an 8-byte slice into a semiblock.
+fn semiblock(bytes: &[u8]) -> [u8; SEMIBLOCK_SIZE] {
+	let mut half = [0u8; SEMIBLOCK_SIZE];
+	half.copy_from_slice(bytes);
+	half
+}
+
+/// XORs the step counter `t`, as a big-endian 64-bit integer, into A.
+fn xor_counter(a: [u8; SEMIBLOCK_SIZE], t: u64) -> [u8; SEMIBLOCK_SIZE] {
+	(u64::from_be_bytes(a) ^ t).to_be_bytes()
+}
@@ -0,0 +1,1052 @@
+//! In Module 1, we discussed Block ciphers like AES. Block ciphers have a fixed length input.
+//! Real wold data that we wish to encrypt _may_ be exactly the right length, but is probably not.
+//! When your data is too short, you can simply pad it up to the correct length.
+//! When your data is too long, you have some options.
+//!
+//! In this exercise, we will explore a few of the common ways that large pieces of data can be
+//! broken up and combined in order to encrypt it with a fixed-length block cipher.
+//!
+//! WARNING: ECB MODE IS NOT SECURE.
+//! Seriously, ECB is NOT secure. Don't use it irl. We are implementing it here to understand _why_
+//! it is not secure and make the point that the most straight-forward approach isn't always the
+//! best, and can sometimes be trivially broken.
+
+use aes::{
+	cipher::{consts::U16, generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit},
+	Aes128, Aes192, Aes256,
+};
+
+/// Enters a `debug` level tracing span for the rest of the enclosing mode function.
+/// Compiles to nothing unless the `tracing` feature is enabled.
+///
+/// Only the mode name, the input length and the number of blocks are recorded. Never pass
+/// keys, IVs or any plaintext/ciphertext bytes in here.
+///
+/// Defined before the `mod` declarations so the modes in submodules can use it too.
+macro_rules! mode_span {
+	($name:literal, $mode:literal, $input_len:expr, $blocks:expr) => {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::debug_span!(
+			$name,
+			mode = $mode,
+			input_len = $input_len,
+			blocks = $blocks
+		)
+		.entered();
+	};
+	// For the streaming functions, which only know the lengths once the input runs out. They
+	// record `input_len` and `blocks` on the current span at the end.
+	($name:literal, $mode:literal) => {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::debug_span!(
+			$name,
+			mode = $mode,
+			input_len = tracing::field::Empty,
+			blocks = tracing::field::Empty
+		)
+		.entered();
+	};
+}
+
+mod block_mode;
+mod encoding;
+mod gcm;
+mod key_wrap;
+mod multi_stream;
+mod ocb;
+mod stream;
+#[cfg(feature = "test-util")]
+mod test_util;
+
+pub use block_mode::{BlockMode, Cbc, Ctr, Ecb};
+#[cfg(feature = "base64")]
+pub use encoding::{
+	base64_decode, base64_encode, cbc_decrypt_base64, cbc_encrypt_base64, ctr_decrypt_base64, ctr_encrypt_base64,
+	ecb_decrypt_base64, ecb_encrypt_base64, key_from_base64,
+};
+pub use encoding::{
+	cbc_decrypt_hex, cbc_encrypt_hex, ctr_decrypt_hex, ctr_encrypt_hex, ecb_decrypt_hex, ecb_encrypt_hex, hex_decode,
+	hex_encode, EncodingError,
+};
+pub use gcm::{
+	gcm_decrypt, gcm_decrypt_detached, gcm_decrypt_with_aad_list, gcm_decrypt_with_iv, gcm_decrypt_with_tag_len,
+	gcm_encrypt, gcm_encrypt_detached, gcm_encrypt_with_aad_list, gcm_encrypt_with_iv, gcm_encrypt_with_nonce,
+	gcm_encrypt_with_tag_len, gcm_verify, GCM_MIN_TAG_SIZE, GCM_NONCE_SIZE, GCM_TAG_SIZE,
+};
+pub use key_wrap::{aes_unwrap, aes_wrap, rewrap};
+pub use multi_stream::MultiStreamCipher;
+pub use ocb::{
+	ocb_decrypt, ocb_decrypt_with_aad_list, ocb_encrypt, ocb_encrypt_with_aad_list, OCB_NONCE_SIZE, OCB_TAG_SIZE,
+};
+pub use stream::{cbc_encrypt_stream, ctr_encrypt_stream, ecb_encrypt_stream, EncryptedReader, EncryptedWriter};
+#[cfg(feature = "test-util")]
+pub use test_util::assert_roundtrip;
+
+///AES has 16-byte (128 bit) blocks, whatever the key size.
+pub const BLOCK_SIZE: usize = 16;
+
+/// Why a ciphertext could not be decrypted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecryptError {
+	/// The ciphertext is not a whole number of blocks, or is too short for the mode.
+	InvalidLength,
+	/// The last block does not end in valid padding.
+	InvalidPadding,
+	/// There is nothing to decrypt. Even an empty plaintext encrypts to a padding block under
+	/// ECB and CBC (after the IV for CBC), and to its nonce under CTR.
+	Empty,
+	/// An integrity check failed: the data was modified or the wrong key was used.
+	AuthenticationFailed,
+	/// The ciphertext could decrypt to more than the caller's limit, see ecb_decrypt_bounded.
+	OutputTooLarge,
+	/// Decryption failed, for a reason deliberately not given. Only cbc_decrypt_uniform
+	/// returns this.
+	Failed,
+}
+
+impl std::fmt::Display for DecryptError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			DecryptError::InvalidLength => write!(f, "ciphertext length is not valid for this mode"),
+			DecryptError::InvalidPadding => write!(f, "ciphertext does not end in valid padding"),
+			DecryptError::Empty => write!(f, "ciphertext has no blocks to decrypt"),
+			DecryptError::AuthenticationFailed => write!(f, "ciphertext failed its integrity check"),
+			DecryptError::OutputTooLarge => write!(f, "ciphertext would decrypt to more than the allowed length"),
+			DecryptError::Failed => write!(f, "ciphertext could not be decrypted"),
+		}
+	}
+}
+
+impl std::error::Error for DecryptError {}
+
+/// Expands `key` into an AES 128 key schedule.
+#[cfg(not(feature = "key-cache"))]
+fn aes_cipher(key: &[u8; BLOCK_SIZE]) -> Aes128 {
+	Aes128::new(&GenericArray::from(*key))
+}
+
+/// Expands `key` into an AES 128 key schedule, reusing the last one built on this thread
+/// when the key is the same.
+///
+/// Every call to a mode function expands its key once, so hot paths that encrypt many short
+/// messages under one key spend a lot of time redoing it. The tradeoff is that the expanded key stays in
+/// a thread-local after the call returns, until a different key replaces it or the thread
+/// exits. Only enable the `key-cache` feature if keeping key material around like that is
+/// acceptable for your application.
+///
+/// Only AES-128 keys go through here. AES-192 and AES-256 keys are expanded fresh every time.
+#[cfg(feature = "key-cache")]
+fn aes_cipher(key: &[u8; BLOCK_SIZE]) -> Aes128 {
+	use std::cell::RefCell;
+
+	thread_local! {
+		static CACHE: RefCell<Option<([u8; BLOCK_SIZE], Aes128)>> = const { RefCell::new(None) };
+	}
+
+	CACHE.with(|cache| {
+		let mut cache = cache.borrow_mut();
+		match &*cache {
+			Some((cached_key, cipher)) if cached_key == key => cipher.clone(),
+			_ => {
+				let cipher = Aes128::new(&GenericArray::from(*key));
+				*cache = Some((*key, cipher.clone()));
+				cipher
+			}
+		}
+	})
+}
+
+/// An AES key. Implemented for 16, 24 and 32 byte arrays, which select AES-128, AES-192 and
+/// AES-256. Every mode function takes any of them, so
+/// `ecb_encrypt(plain_text, [0u8; 32])` encrypts with AES-256.
+pub trait AesKey {
+	/// The block cipher for this key size.
+	type Cipher: BlockEncrypt<BlockSize = U16> + BlockDecrypt;
+
+	/// Expands the key into its key schedule.
+	fn cipher(&self) -> Self::Cipher;
+}
+
+impl AesKey for [u8; 16] {
+	type Cipher = Aes128;
+
+	fn cipher(&self) -> Aes128 {
+		aes_cipher(self)
+	}
+}
+
+impl AesKey for [u8; 24] {
+	type Cipher = Aes192;
+
+	fn cipher(&self) -> Aes192 {
+		Aes192::new(&GenericArray::from(*self))
+	}
+}
+
+impl AesKey for [u8; 32] {
+	type Cipher = Aes256;
+
+	fn cipher(&self) -> Aes256 {
+		Aes256::new(&GenericArray::from(*self))
+	}
+}
+
+/// Which implementation of the AES block cipher the `aes` crate picked for this CPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+	/// The AES-NI instructions on x86/x86_64.
+	AesNi,
+	/// The ARMv8 cryptography extensions. Only used when built with `--cfg aes_armv8`.
+	Armv8,
+	/// The portable fixsliced software implementation. It is constant time, but several
+	/// times slower than the hardware instructions.
+	Soft,
+}
+
+/// Reports which AES backend is running the block cipher on this machine.
+///
+/// The `aes` crate doesn't expose its choice, so we repeat the same check it makes: look for
+/// the CPU's AES instructions at runtime, unless the build forces the software version with
+/// `--cfg aes_force_soft`.
+pub fn active_backend() -> Backend {
+	#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(aes_force_soft)))]
+	{
+		cpufeatures::new!(aes_intrinsics, "aes");
+		if aes_intrinsics::get() {
+			return Backend::AesNi;
+		}
+	}
+
+	#[cfg(all(target_arch = "aarch64", aes_armv8, not(aes_force_soft)))]
+	{
+		cpufeatures::new!(aes_intrinsics, "aes");
+		if aes_intrinsics::get() {
+			return Backend::Armv8;
+		}
+	}
+
+	Backend::Soft
+}
+
+/// Simple AES encryption, with the key schedule already expanded.
+/// Helper function to make the core AES block cipher easier to understand.
+///
+/// The mode functions expand the key once per message and use this for each block, rather
+/// than expanding the key again for every block.
+fn aes_encrypt_block<C: BlockEncrypt<BlockSize = U16>>(cipher: &C, data: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+	// Convert the input to the necessary data type
+	let mut block = GenericArray::from(data);
+
+	cipher.encrypt_block(&mut block);
+
+	block.into()
+}
+
+/// Simple AES decryption, with the key schedule already expanded. See aes_encrypt_block.
+fn aes_decrypt_block<C: BlockDecrypt<BlockSize = U16>>(cipher: &C, data: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+	// Convert the input to the necessary data type
+	let mut block = GenericArray::from(data);
+
+	cipher.decrypt_block(&mut block);
+
+	block.into()
+}
+
+/// Generates a fresh random IV block, or the half-block nonce for CTR.
+#[cfg(not(feature = "test-rng"))]
+fn random_iv<T>() -> T
+where
+	rand::distributions::Standard: rand::distributions::Distribution<T>,
+{
+	rand::random()
+}
+
+// Cargo features are unified across the whole dependency graph, so one crate enabling
+// `test-rng` would enable it for every user of this one. Refusing to build it outside debug
+// builds keeps it from ever reaching a release binary.
+#[cfg(all(feature = "test-rng", not(debug_assertions)))]
+compile_error!("the `test-rng` feature makes IVs and nonces predictable and is only allowed in debug builds");
+
+/// Generates the next IV block, or CTR nonce, from a seedable, per-thread generator.
+///
+/// The `test-rng` feature exists so tests can reproduce the output of the random-IV
+/// modes: call `set_test_seed` and the same seed always yields the same sequence of IVs.
+/// Until a thread calls it, IVs on that thread are as random as without the feature.
+/// Predictable IVs break CBC and reused nonces break CTR and GCM, so this feature must never
+/// be enabled in a production build, and release builds refuse to compile with it.
+#[cfg(feature = "test-rng")]
+fn random_iv<T>() -> T
+where
+	rand::distributions::Standard: rand::distributions::Distribution<T>,
+{
+	use rand::Rng;
+
+	test_rng::RNG.with(|rng| match &mut *rng.borrow_mut() {
+		Some(rng) => rng.gen(),
+		None => rand::random(),
+	})
+}
+
+/// Reseeds the IV generator used by `random_iv` on the current thread. Other threads are
+/// unaffected.
+#[cfg(feature = "test-rng")]
+pub fn set_test_seed(seed: u64) {
+	test_rng::RNG.with(|rng| *rng.borrow_mut() = Some(rand::SeedableRng::seed_from_u64(seed)));
+}
+
+#[cfg(feature = "test-rng")]
+mod test_rng {
+	use rand::rngs::StdRng;
+	use std::cell::RefCell;
+
+	thread_local! {
+		/// `None` until `set_test_seed` is called on this thread.
+		pub(super) static RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+	}
+}
+
+/// Before we can begin encrypting our raw data, we need it to be a multiple of the
+/// block length which is 16 bytes (128 bits) in AES128.
+///
+/// The padding algorithm here is actually not trivial. The trouble is that if we just
+/// naively throw a bunch of zeros on the end, there is no way to know, later, whether
+/// those zeros are padding, or part of the message, or some of each.
+///
+/// The scheme works like this. If the data is not a multiple of the block length,  we
+/// compute how many pad bytes we need, and then write that number into the last several bytes.
+/// Later we look at the last byte, and remove that number of bytes.
+///
+/// But if the data _is_ a multiple of the block length, then we have a problem. We don't want
+/// to later look at the last byte and remove part of the data. Instead, in this case, we add
+/// another entire block containing the block length in each byte. In our case,
+/// [16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16]
+///
+/// Empty data counts as a multiple of the block length, so it pads to exactly that one block.
+/// That's why ECB and CBC still produce a block of ciphertext for an empty message.
+pub fn pad(mut data: Vec<u8>) -> Vec<u8> {
+	// When twe have a multiple the second term is 0
+	let number_pad_bytes = BLOCK_SIZE - data.len() % BLOCK_SIZE;
+
+	data.reserve(number_pad_bytes);
+	for _ in 0..number_pad_bytes {
+		data.push(number_pad_bytes as u8);
+	}
+
+	data
+}
+
+/// Groups the data into BLOCK_SIZE blocks. The data must already be a multiple of the
+/// block size, otherwise `InvalidLength` is returned. Call `pad` first on plaintext.
+pub fn group(data: Vec<u8>) -> Result<Vec<[u8; BLOCK_SIZE]>, DecryptError> {
+	if !data.len().is_multiple_of(BLOCK_SIZE) {
+		return Err(DecryptError::InvalidLength);
+	}
+
+	let mut blocks = Vec::with_capacity(data.len() / BLOCK_SIZE);
+	let mut i = 0;
+	while i < data.len() {
+		let mut block: [u8; BLOCK_SIZE] = Default::default();
+		block.copy_from_slice(&data[i..i + BLOCK_SIZE]);
+		blocks.push(block);
+
+		i += BLOCK_SIZE;
+	}
+
+	// Grouping must be lossless: un_group has to give back exactly what came in.
+	debug_assert_eq!(blocks.concat(), data);
+
+	Ok(blocks)
+}
+
+/// Renders `data` the way the block modes see it: padded, then split into 16-byte blocks, one
+/// block per line in hex. Blocks containing padding get a second line marking the pad bytes.
+///
+/// ```text
+/// block 0: 68 65 6c 6c 6f 0b 0b 0b 0b 0b 0b 0b 0b 0b 0b 0b
+///                         ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ padding
+/// ```
+///
+/// This is purely a teaching aid. Run some repetitive data through it and it is easy to
+/// see which blocks ECB will encrypt to the same value.
+pub fn debug_blocks(data: &[u8]) -> String {
+	use std::fmt::Write;
+
+	let padding_start = data.len();
+	let mut out = String::new();
+
+	for (i, block) in group_padded(pad(data.to_vec())).iter().enumerate() {
+		let label = format!("block {i}: ");
+		let hex: Vec<String> = block.iter().map(|byte| format!("{byte:02x}")).collect();
+		let _ = writeln!(out, "{label}{}", hex.join(" "));
+
+		let block_start = i * BLOCK_SIZE;
+		if block_start + BLOCK_SIZE > padding_start {
+			let marks: Vec<&str> = (block_start..block_start + BLOCK_SIZE)
+				.map(|position| if position >= padding_start { "^^" } else { "  " })
+				.collect();
+			let _ = writeln!(out, "{}{} padding", " ".repeat(label.len()), marks.join(" "));
+		}
+	}
+
+	out
+}
+
+/// Groups data that has just come out of `pad`. That is always a whole number of blocks,
+/// so unlike `group` this can't fail.
+fn group_padded(data: Vec<u8>) -> Vec<[u8; BLOCK_SIZE]> {
+	group(data).expect("pad always produces whole blocks")
+}
+
+/// Does the opposite of the group function
+pub fn un_group(blocks: Vec<[u8; BLOCK_SIZE]>) -> Vec<u8> {
+	blocks.concat()
+}
+
+/// Does the opposite of the pad function. Returns `Empty` if there is no data at all, and
+/// `InvalidPadding` unless the data ends in `n` bytes that all equal `n`, for some `n` from 1
+/// to BLOCK_SIZE.
+///
+/// Every decrypt function finishes here, so this is also where the returned plaintext gets
+/// its capacity trimmed to its length. Otherwise the removed padding (up to a whole block)
+/// would still be allocated behind it.
+///
+/// There is deliberately no lenient version that just trusts the pad count byte. The check
+/// is one pass over at most a block, which together with the trim takes about 40ns against
+/// about 550ns to ECB decrypt 1 KiB, so skipping it saves next to nothing. What it would
+/// cost is real: given a wrong key or a corrupted ciphertext it would strip an arbitrary
+/// number of bytes and return garbage without an error.
+pub fn un_pad(mut data: Vec<u8>) -> Result<Vec<u8>, DecryptError> {
+	let padded_len = data.len();
+	let count = *data.last().ok_or(DecryptError::Empty)?;
+
+	// An attacker who can submit modified CBC ciphertexts and tell good padding from bad
+	// recovers the plaintext a byte at a time (the classic padding oracle). Bailing out at the
+	// first wrong byte would leak, through timing, how much of the padding was right. So the
+	// whole last block is always checked, the same way whatever it holds, and the verdict is
+	// only branched on at the end.
+	//
+	// This removes the timing differences. It does not stop a caller from revealing the error
+	// itself; only an authenticated mode like OCB fixes that.
+	let window = padded_len.min(BLOCK_SIZE);
+	let mut invalid = ct_less_than(0, count as usize) ^ 0xff;
+	invalid |= ct_less_than(window, count as usize);
+	for (i, &byte) in data[padded_len - window..].iter().rev().enumerate() {
+		invalid |= ct_less_than(i, count as usize) & (byte ^ count);
+	}
+	if std::hint::black_box(invalid) != 0 {
+		return Err(DecryptError::InvalidPadding);
+	}
+
+	// The pad count byte is one of the bytes it counts, so exactly that many come off.
+	let number_of_bytes_to_remove = count as usize;
+	data.truncate(padded_len - number_of_bytes_to_remove);
+	data.shrink_to_fit();
+	Ok(data)
+}
+
+/// `0xff` if `a < b`, otherwise `0`, without branching. Both must be far below `i64::MAX`,
+/// which holds for the block offsets and pad counts un_pad compares.
+fn ct_less_than(a: usize, b: usize) -> u8 {
+	((a as i64 - b as i64) >> 63) as u8
+}
+
+/// The first mode we will implement is the Electronic Code Book, or ECB mode.
+/// Warning: THIS MODE IS NOT SECURE!!!!
+///
+/// This is probably the first thing you think of when considering how to encrypt
+/// large data. In this mode we simply encrypt each block of data under the same key.
+/// One good thing about this mode is that it is parallelizable. But to see why it is
+/// insecure look at: https://www.ubiqsecurity.com/wp-content/uploads/2022/02/ECB2.png
+pub fn ecb_encrypt<K: AesKey>(plain_text: Vec<u8>, key: K) -> Vec<u8> {
+	mode_span!("ecb_encrypt", "ecb", plain_text.len(), plain_text.len() / BLOCK_SIZE + 1);
+
+	let blocks = group_padded(pad(plain_text));
+
+    let aes = key.cipher();
+    let ciphers:Vec<[u8; BLOCK_SIZE]> = blocks.iter().map(|block| aes_encrypt_block(&aes, *block))
+        .collect();
+
+    un_group(ciphers)
+}
+
+/// Opposite of ecb_encrypt.
+pub fn ecb_decrypt<K: AesKey>(cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+    mode_span!("ecb_decrypt", "ecb", cipher_text.len(), cipher_text.len() / BLOCK_SIZE);
+
+    let cipher_len = cipher_text.len();
+    let ciphers:Vec<[u8; BLOCK_SIZE]> = group(cipher_text)?;
+
+    let aes = key.cipher();
+    let blocks: Vec<[u8; 16]> = ciphers.iter().map(|cipher| aes_decrypt_block(&aes, *cipher)).collect();
+
+    let padded = un_group(blocks);
+    let pad_count = padded.last().copied().map_or(0, usize::from);
+    let plain_text = un_pad(padded)?;
+
+    // Worked out from the ciphertext length, not from what un_pad did, so an off-by-one in
+    // un_pad shows up here in debug builds.
+    debug_assert_eq!(plain_text.len(), cipher_len - pad_count);
+    Ok(plain_text)
+}
+
+/// Compares two ciphertexts for equality without exiting early on the first differing byte,
+/// so the time taken doesn't reveal how long a common prefix they share.
+///
+/// Ciphertext equality only tells you something about the plaintexts for deterministic modes
+/// like ECB, where the same key and plaintext always give the same ciphertext (which is
+/// exactly why ECB leaks). CBC and CTR use a fresh random IV each time, so two encryptions
+/// of the same message will practically never compare equal.
+///
+/// The lengths are not treated as secret: inputs of different lengths return `false` right away.
+pub fn ct_ciphertext_eq(a: &[u8], b: &[u8]) -> bool {
+	if a.len() != b.len() {
+		return false;
+	}
+
+	let difference = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+
+	std::hint::black_box(difference) == 0
+}
+
+/// Encodes a list of associated data fields as one unambiguous byte string, for the
+/// `*_with_aad_list` variants of the authenticated modes. Each field is its length as 8
+/// big-endian bytes followed by the field itself.
+///
+/// Concatenating the fields instead would let `["ab", "c"]` and `["a", "bc"]` authenticate
+/// as the same thing, so an attacker could move bytes between a header and a footer
+/// without the tag noticing. With the lengths in front no two lists encode the same.
+pub fn encode_aad_list(fields: &[&[u8]]) -> Vec<u8> {
+	let mut encoded = Vec::with_capacity(fields.iter().map(|field| 8 + field.len()).sum());
+	for field in fields {
+		encoded.extend_from_slice(&(field.len() as u64).to_be_bytes());
+		encoded.extend_from_slice(field);
+	}
+
+	encoded
+}
+
+/// Counts how many 16-byte blocks of `cipher_text` are repeats of an earlier block, so
+/// `[A, B, A, A]` gives 2. A trailing partial block is ignored.
+///
+/// Under ECB every repeated plaintext block becomes a repeated ciphertext block, so this
+/// number says how much structure is leaking. For CBC or CTR output it should be 0: a
+/// repeat there is astronomically unlikely and more likely points at corrupted data.
+pub fn repeated_block_count(cipher_text: &[u8]) -> usize {
+	let mut seen = std::collections::HashSet::new();
+
+	cipher_text
+		.chunks_exact(BLOCK_SIZE)
+		.filter(|block| !seen.insert(*block))
+		.count()
+}
+
+/// Whether any 16-byte block of `cipher_text` repeats, the classic fingerprint of ECB. Same
+/// as `repeated_block_count(cipher_text) > 0`, but stops at the first repeat.
+pub fn has_repeated_blocks(cipher_text: &[u8]) -> bool {
+	let mut seen = std::collections::HashSet::new();
+
+	!cipher_text.chunks_exact(BLOCK_SIZE).all(|block| seen.insert(block))
+}
+
+/// The next mode, which you can implement on your own is cipherblock chaining.
+/// This mode actually is secure, and it often used in real world applications.
+///
+/// In this mode, the ciphertext from the first block is XORed with the
+/// plaintext of the next block before it is encrypted.
+///
+/// For more information, and a very clear diagram,
+/// see https://de.wikipedia.org/wiki/Cipher_Block_Chaining_Mode
+///
+/// You will need to generate a random initialization vector (IV) to encrypt the
+/// very first block because it doesn't have a previous block. Typically this IV
+/// is inserted as the first block of ciphertext.
+pub fn cbc_encrypt<K: AesKey>(plain_text: Vec<u8>, key: K) -> Vec<u8> {
+	// Remember to generate a random initialization vector for the first block.
+	cbc_encrypt_with_iv(plain_text, key, random_iv())
+}
+
+/// Same as cbc_encrypt, but with the IV supplied by the caller. The IV is still prepended to
+/// the ciphertext.
+///
+/// This is meant for reproducing known test vectors. A CBC IV must be unpredictable, so
+/// anything else should use cbc_encrypt and let it pick a random one.
+pub fn cbc_encrypt_with_iv<K: AesKey>(plain_text: Vec<u8>, key: K, iv: [u8; BLOCK_SIZE]) -> Vec<u8> {
+	mode_span!("cbc_encrypt", "cbc", plain_text.len(), plain_text.len() / BLOCK_SIZE + 1);
+
+	let mut ciphers = Vec::with_capacity(plain_text.len() / BLOCK_SIZE + 2);
+	ciphers.push(iv); // inserts the IV in the first block
+	cbc_encrypt_blocks(plain_text, key, iv, ciphers)
+}
+
+/// Same as cbc_encrypt, but hands the random IV back separately instead of prepending it,
+/// for callers that store the IV in its own field. Decrypt with cbc_decrypt_detached.
+pub fn cbc_encrypt_with_generated_iv<K: AesKey>(plain_text: Vec<u8>, key: K) -> ([u8; BLOCK_SIZE], Vec<u8>) {
+	mode_span!("cbc_encrypt", "cbc", plain_text.len(), plain_text.len() / BLOCK_SIZE + 1);
+
+	let iv = random_iv();
+	let ciphers = Vec::with_capacity(plain_text.len() / BLOCK_SIZE + 1);
+	(iv, cbc_encrypt_blocks(plain_text, key, iv, ciphers))
+}
+
+/// The CBC chain itself: pads `plain_text` and appends its encrypted blocks to `ciphers`,
+/// starting the chain from `iv`.
+fn cbc_encrypt_blocks<K: AesKey>(
+	plain_text: Vec<u8>,
+	key: K,
+	iv: [u8; BLOCK_SIZE],
+	mut ciphers: Vec<[u8; BLOCK_SIZE]>,
+) -> Vec<u8> {
+	let blocks = group_padded(pad(plain_text));
+
+    let mut nonce:[u8; BLOCK_SIZE] = iv;
+    let aes = key.cipher();
+
+    // Each plaintext block is chained onto the ciphertext block before it, starting with the IV.
+    for block in blocks {
+        nonce = aes_encrypt_block(&aes, xor_arrays(block, nonce));
+        ciphers.push(nonce);
+    }
+
+    un_group(ciphers)
+}
+
+/// XORs two blocks together, as one 128-bit word rather than byte by byte.
+///
+/// Both blocks are read and the result written back in native byte order, so every output
+/// byte is still just the XOR of the two input bytes in the same position.
+fn xor_arrays(array1: [u8; BLOCK_SIZE], array2: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+	(u128::from_ne_bytes(array1) ^ u128::from_ne_bytes(array2)).to_ne_bytes()
+}
+
+/// Splits 32 bytes of stored key material into the AES-128 key (the first 16 bytes) and the
+/// CBC IV (the last 16), for callers that keep the two together. Returns `InvalidLength`
+/// for anything but exactly 32 bytes, instead of slicing out a short key or IV.
+///
+/// A stored IV only suits a single message, e.g. reproducing one that was encrypted
+/// earlier. Encrypting again with the same key and IV leaks which leading blocks the
+/// messages share, so new messages should go through cbc_encrypt.
+pub fn split_key_iv(combined: &[u8]) -> Result<([u8; BLOCK_SIZE], [u8; BLOCK_SIZE]), DecryptError> {
+	let combined: &[u8; 2 * BLOCK_SIZE] = combined.try_into().map_err(|_| DecryptError::InvalidLength)?;
+	let (key, iv) = combined.split_at(BLOCK_SIZE);
+
+	Ok((key.try_into().expect("split in half"), iv.try_into().expect("split in half")))
+}
+
+pub fn cbc_decrypt<K: AesKey>(cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+    mode_span!("cbc_decrypt", "cbc", cipher_text.len(), (cipher_text.len() / BLOCK_SIZE).saturating_sub(1));
+
+    let mut ciphers:Vec<[u8; BLOCK_SIZE]> = group(cipher_text)?;
+    if ciphers.is_empty() {
+        return Err(DecryptError::Empty);
+    }
+
+    // retreive nonce and remove it
+    let nonce:[u8; BLOCK_SIZE] = ciphers.remove(0);
+
+    cbc_decrypt_blocks(ciphers, key, nonce)
+}
+
+/// Opposite of cbc_encrypt_with_generated_iv: decrypts a ciphertext that doesn't start with
+/// its IV, using the `iv` stored alongside it.
+pub fn cbc_decrypt_detached<K: AesKey>(cipher_text: Vec<u8>, key: K, iv: [u8; BLOCK_SIZE]) -> Result<Vec<u8>, DecryptError> {
+	mode_span!("cbc_decrypt", "cbc", cipher_text.len(), cipher_text.len() / BLOCK_SIZE);
+
+	cbc_decrypt_blocks(group(cipher_text)?, key, iv)
+}
+
+/// Undoes cbc_encrypt_blocks: decrypts `ciphers` with the chain starting from `iv`, then
+/// removes the padding.
+fn cbc_decrypt_blocks<K: AesKey>(ciphers: Vec<[u8; BLOCK_SIZE]>, key: K, iv: [u8; BLOCK_SIZE]) -> Result<Vec<u8>, DecryptError> {
+    let cipher_len = ciphers.len() * BLOCK_SIZE;
+    let mut nonce = iv;
+    let aes = key.cipher();
+    let mut blocks: Vec<[u8; 16]> = Vec::with_capacity(ciphers.len());
+
+    // Each block is XORed with the ciphertext block before it, not with the plaintext.
+    for cipher in ciphers {
+        let block = aes_decrypt_block(&aes, cipher);
+        blocks.push(xor_arrays(block, nonce));
+        nonce = cipher;
+    }
+
+    let padded = un_group(blocks);
+    let pad_count = padded.last().copied().map_or(0, usize::from);
+    let plain_text = un_pad(padded)?;
+
+    // The same independent check as in ecb_decrypt. `cipher_len` doesn't count the IV.
+    debug_assert_eq!(plain_text.len(), cipher_len - pad_count);
+    Ok(plain_text)
+}
+
+/// Same as cbc_decrypt, but every failure is the same `Failed` error, whether the length or
+/// the padding was wrong. For CBC without a MAC, where telling bad padding apart from other
+/// errors is what makes a padding oracle.
+///
+/// The work doesn't depend on what went wrong either. A ciphertext with a bad length is
+/// still decrypted and unpadded as far as it goes, topped up to an IV and one block of
+/// zeros if it is shorter than that, and the verdict is only branched on at the end. The
+/// time still grows with the length, but the length isn't secret.
+///
+/// This only goes part of the way. Whether decryption succeeded at all is still visible,
+/// and a wrong guess at the padding fails where a right one succeeds, so a caller that acts
+/// on the result where an attacker can see it is still an oracle. The real fix is an
+/// authenticated mode like gcm_encrypt or ocb_encrypt, which rejects modified ciphertext
+/// before looking at the plaintext.
+pub fn cbc_decrypt_uniform<K: AesKey>(mut cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+	mode_span!("cbc_decrypt", "cbc", cipher_text.len(), (cipher_text.len() / BLOCK_SIZE).saturating_sub(1));
+
+	let well_formed = cipher_text.len().is_multiple_of(BLOCK_SIZE) && cipher_text.len() >= 2 * BLOCK_SIZE;
+	let whole_blocks = cipher_text.len() - cipher_text.len() % BLOCK_SIZE;
+	cipher_text.resize(whole_blocks.max(2 * BLOCK_SIZE), 0);
+
+	let mut ciphers = group_padded(cipher_text);
+	let nonce = ciphers.remove(0);
+	let result = cbc_decrypt_blocks(ciphers, key, nonce);
+
+	result.ok().filter(|_| well_formed).ok_or(DecryptError::Failed)
+}
+
+/// Reports whether `cipher_text` CBC decrypts to valid padding under `key`, and nothing else.
+/// For demonstrating the padding oracle attack in examples/padding_oracle.rs only.
+///
+/// A server that reacts differently to bad padding than to other failures is exactly this
+/// function, exposed to whoever sends it ciphertexts, and that is enough for them to decrypt
+/// any CBC ciphertext without the key. The constant-time un_pad only hides the difference in
+/// timing, not in the answer.
+#[cfg(feature = "demo")]
+pub fn padding_oracle<K: AesKey>(cipher_text: &[u8], key: K) -> bool {
+	cbc_decrypt(cipher_text.to_vec(), key).is_ok()
+}
+
+/// Another mode which you can implement on your own is counter mode.
+/// This mode is secure as well, and is used in real world applications.
+/// It allows parallelized encryption and decryption, as well as random read access when decrypting.
+///
+/// In this mode, there is an index for each block being encrypted (the "counter"), as well as a random nonce.
+/// For a 128-bit cipher, the nonce is 64 bits long, and so is the counter: the nonce fills the
+/// high 8 bytes of V and the big-endian counter the low 8. The counter starts at 0 for every
+/// message and never carries into the nonce half. Running out of counter values would mean
+/// reusing keystream, so it panics instead, but that takes 2^64 blocks.
+///
+/// For the ith block, the 128-bit value V of `nonce | counter` is constructed, where | denotes
+/// concatenation. Then, V is encrypted with the key using ECB mode. Finally, the encrypted V is
+/// XOR'd with the plaintext to produce the ciphertext.
+///
+/// A very clear diagram is present here:
+/// https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#Counter_(CTR)
+///
+/// Once again, you will need to generate a random nonce which is 64 bits long. Its 8 bytes are
+/// inserted at the front of the ciphertext.
+///
+/// Because the plaintext is only ever XORed with the keystream, CTR doesn't need padding: the
+/// last keystream block is cut to the length of the last plaintext block, and the ciphertext
+/// is exactly 8 bytes (the nonce) longer than the plaintext.
+pub fn ctr_encrypt<K: AesKey>(plain_text: Vec<u8>, key: K) -> Vec<u8> {
+	ctr_encrypt_with_layout(plain_text, key, CounterLayout::default())
+}
+
+/// The order in which the nonce and the counter are concatenated to build V.
+///
+/// This crate uses `nonce | counter`, but some implementations put the counter first. Picking
+/// the matching layout is all that's needed to interoperate with them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CounterLayout {
+	/// V = `nonce | counter`. This is what `ctr_encrypt` and `ctr_decrypt` use.
+	#[default]
+	NonceThenCounter,
+	/// V = `counter | nonce`.
+	CounterThenNonce,
+}
+
+impl CounterLayout {
+	/// Builds V for the given nonce half and counter bytes.
+	fn counter_block(self, nonce: [u8; BLOCK_SIZE / 2], counter: [u8; BLOCK_SIZE / 2]) -> [u8; BLOCK_SIZE] {
+		match self {
+			CounterLayout::NonceThenCounter => concat_arrays(nonce, counter),
+			CounterLayout::CounterThenNonce => concat_arrays(counter, nonce),
+		}
+	}
+}
+
+/// Same as ctr_encrypt, but with the nonce/counter order of V chosen by `layout`.
+pub fn ctr_encrypt_with_layout<K: AesKey>(plain_text: Vec<u8>, key: K, layout: CounterLayout) -> Vec<u8> {
+	// Remember to generate a random nonce
+	ctr_encrypt_with_nonce_and_layout(plain_text, key, random_iv(), layout)
+}
+
+/// Same as ctr_encrypt, but with the 64-bit nonce supplied by the caller. It is still
+/// prepended to the ciphertext.
+///
+/// This is meant for reproducing known test vectors. Encrypting two messages under the same
+/// key and nonce reuses the keystream and leaks the XOR of the plaintexts, so anything else
+/// should use ctr_encrypt and let it pick a random nonce.
+pub fn ctr_encrypt_with_nonce<K: AesKey>(plain_text: Vec<u8>, key: K, nonce: [u8; BLOCK_SIZE / 2]) -> Vec<u8> {
+	ctr_encrypt_with_nonce_and_layout(plain_text, key, nonce, CounterLayout::default())
+}
+
+/// Same as ctr_encrypt, but with the nonce derived from the sender's message counter: it is
+/// `message_seq` as 8 big-endian bytes. Encrypting the same message under the same sequence
+/// number gives the same ciphertext, which makes retries idempotent.
+///
+/// The nonces are unique as long as `message_seq` never repeats under the same key, so the
+/// counter must survive restarts and must not be shared with anything else encrypting
+/// under the key, ctr_encrypt's random nonces included.
+pub fn ctr_encrypt_seq<K: AesKey>(plain_text: Vec<u8>, key: K, message_seq: u64) -> Vec<u8> {
+	ctr_encrypt_with_nonce(plain_text, key, message_seq.to_be_bytes())
+}
+
+/// Same as ctr_encrypt, but the nonce starts with `unix_time` as 4 big-endian bytes, and
+/// only the other 4 are random. Nonces then sort by the second they were made in, which
+/// suits telemetry pipelines collecting from many senders under one key.
+///
+/// The price is uniqueness. Messages from different seconds can never share a nonce, but
+/// within one second the 32 random bits are all that keeps them apart: n messages collide
+/// with probability about n^2 / 2^33, so around 1 in 100 for 9,000 messages in the same
+/// second across every sender. ctr_encrypt's 64 random bits give the same odds only after
+/// about 600 million messages in total. Senders with clocks set back also reuse the seconds
+/// they already used. Above a few thousand messages a second, use ctr_encrypt_seq with a
+/// counter per sender instead.
+pub fn ctr_encrypt_timestamped<K: AesKey>(plain_text: Vec<u8>, key: K, unix_time: u32) -> Vec<u8> {
+	let random: u32 = random_iv();
+	ctr_encrypt_with_nonce(plain_text, key, ((u64::from(unix_time) << 32) | u64::from(random)).to_be_bytes())
+}
+
+/// The CTR encryption that the public variants share.
+fn ctr_encrypt_with_nonce_and_layout<K: AesKey>(
+	plain_text: Vec<u8>,
+	key: K,
+	nonce: [u8; BLOCK_SIZE / 2],
+	layout: CounterLayout,
+) -> Vec<u8> {
+	mode_span!("ctr_encrypt", "ctr", plain_text.len(), plain_text.len().div_ceil(BLOCK_SIZE));
+
+    let mut cipher_text = Vec::with_capacity(BLOCK_SIZE / 2 + plain_text.len());
+    cipher_text.extend_from_slice(&nonce); // adding the 64 bit nonce in the front
+    cipher_text.extend_from_slice(&plain_text);
+    ctr_apply_keystream(&mut cipher_text[BLOCK_SIZE / 2..], &key.cipher(), nonce, layout);
+    cipher_text
+}
+
+/// XORs the CTR keystream for `nonce` into `data`, in place. The last keystream block is
+/// cut short to fit the data. Encrypting and decrypting are the same thing.
+fn ctr_apply_keystream<C: BlockEncrypt<BlockSize = U16>>(
+	data: &mut [u8],
+	cipher: &C,
+	nonce: [u8; BLOCK_SIZE / 2],
+	layout: CounterLayout,
+) {
+    let mut counter: u64 = 0;
+
+    for chunk in data.chunks_mut(BLOCK_SIZE) {
+        // encrypt V. CTR never uses the inverse cipher, not even to decrypt.
+        let encypted_v = aes_encrypt_block(cipher, layout.counter_block(nonce, counter_to_bytes(counter)));
+
+        chunk.iter_mut().zip(encypted_v).for_each(|(byte, key_byte)| *byte ^= key_byte); // xor data with encrypted V
+        counter = increment_counter(counter);
+    }
+}
+
+/// Encodes the CTR block counter as the 8 big-endian bytes that go into the low half of V.
+pub fn counter_to_bytes(counter: u64) -> [u8; BLOCK_SIZE / 2] {
+	counter.to_be_bytes()
+}
+
+/// Does the opposite of counter_to_bytes.
+pub fn bytes_to_counter(bytes: &[u8; BLOCK_SIZE / 2]) -> u64 {
+	u64::from_be_bytes(*bytes)
+}
+
+/// Moves the CTR block counter on by one. Wrapping around would start reusing keystream
+/// blocks, so running out of counter space is treated as a bug rather than silently wrapped.
+fn increment_counter(counter: u64) -> u64 {
+	counter.checked_add(1).expect("CTR block counter overflowed")
+}
+
+/// Joins two half blocks into one: `first` fills the first 8 bytes and `second` the last 8.
+/// CTR uses this to build V from the 64-bit nonce and the big-endian counter, in the order
+/// given by its CounterLayout.
+fn concat_arrays(first: [u8; BLOCK_SIZE / 2], second: [u8; BLOCK_SIZE / 2]) -> [u8; BLOCK_SIZE] {
+	let mut result = [0u8; BLOCK_SIZE];
+
+	result[..BLOCK_SIZE / 2].copy_from_slice(&first);
+	result[BLOCK_SIZE / 2..].copy_from_slice(&second);
+
+	result
+}
+
+pub fn ctr_decrypt<K: AesKey>(cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+    ctr_decrypt_with_layout(cipher_text, key, CounterLayout::default())
+}
+
+/// Opposite of ctr_encrypt_with_layout. `layout` must match the one used to encrypt.
+pub fn ctr_decrypt_with_layout<K: AesKey>(mut cipher_text: Vec<u8>, key: K, layout: CounterLayout) -> Result<Vec<u8>, DecryptError> {
+    ctr_decrypt_in_place_with_layout(&mut cipher_text, key, layout)?;
+    Ok(cipher_text)
+}
+
+/// Same as ctr_decrypt, but decrypts `buf` where it is: the keystream is XORed straight into
+/// the data and the nonce in front is then shifted out, so nothing is allocated. On error
+/// `buf` is left as it was.
+pub fn ctr_decrypt_in_place<K: AesKey>(buf: &mut Vec<u8>, key: K) -> Result<(), DecryptError> {
+	ctr_decrypt_in_place_with_layout(buf, key, CounterLayout::default())
+}
+
+/// The CTR decryption that the public variants share.
+fn ctr_decrypt_in_place_with_layout<K: AesKey>(buf: &mut Vec<u8>, key: K, layout: CounterLayout) -> Result<(), DecryptError> {
+    mode_span!("ctr_decrypt", "ctr", buf.len(), buf.len().saturating_sub(BLOCK_SIZE / 2).div_ceil(BLOCK_SIZE));
+
+    if buf.is_empty() {
+        return Err(DecryptError::Empty);
+    }
+    if buf.len() < BLOCK_SIZE / 2 {
+        return Err(DecryptError::InvalidLength);
+    }
+
+    // retreive nonce, the data follows it. There is no padding to check or remove.
+    let (nonce, data) = buf.split_at_mut(BLOCK_SIZE / 2);
+    let nonce: [u8; BLOCK_SIZE / 2] = (&*nonce).try_into().expect("split off exactly the nonce");
+
+    ctr_apply_keystream(data, &key.cipher(), nonce, layout);
+    buf.drain(..BLOCK_SIZE / 2);
+    Ok(())
+}
+
+/// Decrypts only the first `n` bytes of a ctr_encrypt ciphertext, or all of it if it is
+/// shorter, e.g. to check a file's magic number without decrypting the whole file. Only
+/// the keystream blocks covering those bytes are generated.
+///
+/// Nothing authenticates CTR ciphertext, so a matching prefix shows the key and file are
+/// probably the expected ones, not that the file is intact.
+pub fn ctr_decrypt_prefix<K: AesKey>(cipher_text: &[u8], key: K, n: usize) -> Result<Vec<u8>, DecryptError> {
+	if cipher_text.is_empty() {
+		return Err(DecryptError::Empty);
+	}
+	if cipher_text.len() < BLOCK_SIZE / 2 {
+		return Err(DecryptError::InvalidLength);
+	}
+
+	let (nonce, data) = cipher_text.split_at(BLOCK_SIZE / 2);
+	let data = &data[..n.min(data.len())];
+	mode_span!("ctr_decrypt_prefix", "ctr", data.len(), data.len().div_ceil(BLOCK_SIZE));
+
+	let mut plain_text = data.to_vec();
+	ctr_apply_keystream(
+		&mut plain_text,
+		&key.cipher(),
+		nonce.try_into().expect("split off exactly the nonce"),
+		CounterLayout::default(),
+	);
+	Ok(plain_text)
+}
+
+/// Same as ecb_decrypt, but returns `OutputTooLarge` without decrypting anything if
+/// `cipher_text` could hold more than `max_output_len` bytes of plaintext. For services that
+/// decrypt attacker-supplied data, where an oversized message would otherwise cost a
+/// plaintext buffer as large as itself.
+///
+/// The check goes by length alone, so it runs before any buffer is allocated. ECB padding
+/// is at least one byte, so a ciphertext of `n` bytes holds at most `n - 1`.
+pub fn ecb_decrypt_bounded<K: AesKey>(cipher_text: Vec<u8>, key: K, max_output_len: usize) -> Result<Vec<u8>, DecryptError> {
+	check_output_len(cipher_text.len().saturating_sub(1), max_output_len)?;
+	ecb_decrypt(cipher_text, key)
+}
+
+/// Same as cbc_decrypt, but bounded like ecb_decrypt_bounded. The IV block and at least one
+/// byte of padding don't count towards the plaintext.
+pub fn cbc_decrypt_bounded<K: AesKey>(cipher_text: Vec<u8>, key: K, max_output_len: usize) -> Result<Vec<u8>, DecryptError> {
+	check_output_len(cipher_text.len().saturating_sub(BLOCK_SIZE + 1), max_output_len)?;
+	cbc_decrypt(cipher_text, key)
+}
+
+/// Same as ctr_decrypt, but bounded like ecb_decrypt_bounded. CTR isn't padded, so the
+/// plaintext is exactly the ciphertext minus its nonce.
+pub fn ctr_decrypt_bounded<K: AesKey>(cipher_text: Vec<u8>, key: K, max_output_len: usize) -> Result<Vec<u8>, DecryptError> {
+	check_output_len(cipher_text.len().saturating_sub(BLOCK_SIZE / 2), max_output_len)?;
+	ctr_decrypt(cipher_text, key)
+}
+
+fn check_output_len(longest_output: usize, max_output_len: usize) -> Result<(), DecryptError> {
+	if longest_output > max_output_len {
+		return Err(DecryptError::OutputTooLarge);
+	}
+	Ok(())
+}
+
+/// Same as ecb_decrypt, but the plaintext is wiped from memory when the returned buffer is dropped.
+///
+/// Only the returned buffer is wiped. The intermediate vectors used while decrypting, and
+/// the allocation given up when the plaintext is shrunk after unpadding, are freed as usual.
+#[cfg(feature = "zeroize")]
+pub fn ecb_decrypt_zeroizing<K: AesKey>(cipher_text: Vec<u8>, key: K) -> Result<zeroize::Zeroizing<Vec<u8>>, DecryptError> {
+	ecb_decrypt(cipher_text, key).map(zeroize::Zeroizing::new)
+}
+
+/// Same as cbc_decrypt, but the plaintext is wiped from memory when the returned buffer is dropped.
+/// See ecb_decrypt_zeroizing for what is and isn't covered.
+#[cfg(feature = "zeroize")]
+pub fn cbc_decrypt_zeroizing<K: AesKey>(cipher_text: Vec<u8>, key: K) -> Result<zeroize::Zeroizing<Vec<u8>>, DecryptError> {
+	cbc_decrypt(cipher_text, key).map(zeroize::Zeroizing::new)
+}
+
+/// Same as ctr_decrypt, but the plaintext is wiped from memory when the returned buffer is dropped.
+/// See ecb_decrypt_zeroizing for what is and isn't covered.
+#[cfg(feature = "zeroize")]
+pub fn ctr_decrypt_zeroizing<K: AesKey>(cipher_text: Vec<u8>, key: K) -> Result<zeroize::Zeroizing<Vec<u8>>, DecryptError> {
+	ctr_decrypt(cipher_text, key).map(zeroize::Zeroizing::new)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn increment_counter_carries_across_bytes() {
+		assert_eq!(counter_to_bytes(increment_counter(0xff)), [0, 0, 0, 0, 0, 0, 1, 0]);
+		assert_eq!(counter_to_bytes(increment_counter(u32::MAX as u64)), [0, 0, 0, 1, 0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn increment_counter_counts_through_the_low_two_bytes() {
+		// 70000 is past 2^16, so the carry has to reach the third byte.
+		let counter = (0..70000).fold(0, |counter, _| increment_counter(counter));
+
+		assert_eq!(counter_to_bytes(counter), [0, 0, 0, 0, 0, 0x01, 0x11, 0x70]);
+		assert_eq!(counter_to_bytes(counter), 70000u64.to_be_bytes());
+	}
+
+	#[test]
+	fn increment_counter_reaches_the_last_counter_value() {
+		assert_eq!(counter_to_bytes(increment_counter(u64::MAX - 1)), [0xff; BLOCK_SIZE / 2]);
+	}
+
+	#[test]
+	#[should_panic(expected = "CTR block counter overflowed")]
+	fn increment_counter_panics_instead_of_wrapping() {
+		increment_counter(u64::MAX);
+	}
+
+	#[test]
+	fn xor_arrays_matches_a_byte_wise_xor() {
+		for _ in 0..1000 {
+			let (a, b): ([u8; BLOCK_SIZE], [u8; BLOCK_SIZE]) = (rand::random(), rand::random());
+			let mut expected = [0u8; BLOCK_SIZE];
+			for i in 0..BLOCK_SIZE {
+				expected[i] = a[i] ^ b[i];
+			}
+
+			assert_eq!(xor_arrays(a, b), expected, "{a:?} ^ {b:?}");
+		}
+	}
+
+	#[test]
+	fn concat_arrays_puts_the_first_half_first() {
+		let nonce = *b"8 bytes!";
+		let counter = counter_to_bytes(0x0102);
+		let v = concat_arrays(nonce, counter);
+
+		assert_eq!(v[..BLOCK_SIZE / 2], nonce);
+		assert_eq!(v[BLOCK_SIZE / 2..], [0, 0, 0, 0, 0, 0, 1, 2]);
+		assert_eq!(CounterLayout::NonceThenCounter.counter_block(nonce, counter), v);
+		assert_eq!(CounterLayout::CounterThenNonce.counter_block(nonce, counter), concat_arrays(counter, nonce));
+	}
+}
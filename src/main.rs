@@ -1,280 +1,216 @@
-//! In Module 1, we discussed Block ciphers like AES. Block ciphers have a fixed length input.
-//! Real wold data that we wish to encrypt _may_ be exactly the right length, but is probably not.
-//! When your data is too short, you can simply pad it up to the correct length.
-//! When your data is too long, you have some options.
+//! Command line front end for the `aes_modes` library.
 //!
-//! In this exercise, we will explore a few of the common ways that large pieces of data can be
-//! broken up and combined in order to encrypt it with a fixed-length block cipher.
+//! ```text
+//! aes-mode-activity encrypt --mode cbc --key <hex> --in plain.bin --out cipher.bin
+//! aes-mode-activity decrypt --mode cbc --key <hex> --in cipher.bin --out plain.bin
+//! ```
 //!
-//! WARNING: ECB MODE IS NOT SECURE.
-//! Seriously, ECB is NOT secure. Don't use it irl. We are implementing it here to understand _why_
-//! it is not secure and make the point that the most straight-forward approach isn't always the
-//! best, and can sometimes be trivially broken.
-
-use aes::{
-	cipher::{generic_array::GenericArray, BlockCipher, BlockDecrypt, BlockEncrypt, KeyInit},
-	Aes128,
+//! The key is given as 32, 48 or 64 hex characters, for AES-128, AES-192 or AES-256.
+//! Anything on the command line is visible to other users in `ps`, so the key can instead be
+//! read from an environment variable with `--key-env <name>`. When built with the `base64`
+//! feature, `--key-base64 <base64>` takes the key as base64 instead of hex.
+//!
+//! With `--iv-file <file>`, CBC keeps the IV in that file instead of at the front of the
+//! ciphertext: encrypting writes the random IV there and decrypting reads it back, so the
+//! encrypted file is just the padded plaintext.
+
+use std::{
+	env::VarError,
+	fs,
+	path::{Path, PathBuf},
+	process::ExitCode,
 };
 
-use rand::Rng;
+use aes_modes::{
+	cbc_decrypt, cbc_decrypt_detached, cbc_encrypt, cbc_encrypt_with_generated_iv, ctr_decrypt, ctr_encrypt, ecb_decrypt,
+	ecb_encrypt, hex_decode, AesKey, DecryptError, BLOCK_SIZE,
+};
 
-///We're using AES 128 which has 16-byte (128 bit) blocks.
-const BLOCK_SIZE: usize = 16;
+const USAGE: &str = "usage: aes-mode-activity <encrypt|decrypt> --mode <ecb|cbc|ctr> (--key <hex> | --key-env <name>) --in <file> --out <file> [--iv-file <file>]";
 
-fn main() {
-	todo!("Maybe this should be a library crate. TBD");
+#[derive(Clone, Copy)]
+enum Direction {
+	Encrypt,
+	Decrypt,
 }
 
-/// Simple AES encryption
-/// Helper function to make the core AES block cipher easier to understand.
-fn aes_encrypt(data: [u8; BLOCK_SIZE], key: &[u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
-	// Convert the inputs to the necessary data type
-	let mut block = GenericArray::from(data);
-	let key = GenericArray::from(*key);
-
-	let cipher = Aes128::new(&key);
+#[derive(Clone, Copy)]
+enum Mode {
+	Ecb,
+	Cbc,
+	Ctr,
+}
 
-	cipher.encrypt_block(&mut block);
+struct Args {
+	direction: Direction,
+	mode: Mode,
+	key: Vec<u8>,
+	input: PathBuf,
+	output: PathBuf,
+	iv_file: Option<PathBuf>,
+}
 
-	block.into()
+fn main() -> ExitCode {
+	match run() {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(message) => {
+			eprintln!("error: {message}");
+			ExitCode::FAILURE
+		}
+	}
 }
 
-/// Simple AES encryption
-/// Helper function to make the core AES block cipher easier to understand.
-fn aes_decrypt(data: [u8; BLOCK_SIZE], key: &[u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
-	// Convert the inputs to the necessary data type
-	let mut block = GenericArray::from(data);
-	let key = GenericArray::from(*key);
+fn run() -> Result<(), String> {
+	let args = parse_args(std::env::args().skip(1)).map_err(|message| format!("{message}\n{USAGE}"))?;
 
-	let cipher = Aes128::new(&key);
+	let data = fs::read(&args.input).map_err(|e| format!("could not read {}: {e}", args.input.display()))?;
 
-	cipher.decrypt_block(&mut block);
+	let output = match args.key.len() {
+		16 => crypt(&args, data, key_array::<16>(&args.key)),
+		24 => crypt(&args, data, key_array::<24>(&args.key)),
+		32 => crypt(&args, data, key_array::<32>(&args.key)),
+		_ => unreachable!("parse_args only accepts 16, 24 or 32 byte keys"),
+	}?;
 
-	block.into()
+	fs::write(&args.output, output).map_err(|e| format!("could not write {}: {e}", args.output.display()))
 }
 
-/// Before we can begin encrypting our raw data, we need it to be a multiple of the
-/// block length which is 16 bytes (128 bits) in AES128.
-///
-/// The padding algorithm here is actually not trivial. The trouble is that if we just
-/// naively throw a bunch of zeros on the end, there is no way to know, later, whether
-/// those zeros are padding, or part of the message, or some of each.
-///
-/// The scheme works like this. If the data is not a multiple of the block length,  we
-/// compute how many pad bytes we need, and then write that number into the last several bytes.
-/// Later we look at the last byte, and remove that number of bytes.
-///
-/// But if the data _is_ a multiple of the block length, then we have a problem. We don't want
-/// to later look at the last byte and remove part of the data. Instead, in this case, we add
-/// another entire block containing the block length in each byte. In our case,
-/// [16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16, 16]
-fn pad(mut data: Vec<u8>) -> Vec<u8> {
-	// When twe have a multiple the second term is 0
-	let number_pad_bytes = BLOCK_SIZE - data.len() % BLOCK_SIZE;
+fn crypt<K: AesKey>(args: &Args, data: Vec<u8>, key: K) -> Result<Vec<u8>, String> {
+	let decrypted = |result: Result<Vec<u8>, DecryptError>| {
+		result.map_err(|e| format!("could not decrypt {}: {e}", args.input.display()))
+	};
 
-	for _ in 0..number_pad_bytes {
-		data.push(number_pad_bytes as u8);
+	match &args.iv_file {
+		Some(iv_file) => crypt_with_iv_file(args.direction, data, key, iv_file, decrypted),
+		None => decrypted(crypt_with_prepended_iv(args.direction, args.mode, data, key)),
 	}
-
-	data
 }
 
-/// Groups the data into BLOCK_SIZE blocks. Assumes the data is already
-/// a multiple of the block size. If this is not the case, call `pad` first.
-fn group(data: Vec<u8>) -> Vec<[u8; BLOCK_SIZE]> {
-	let mut blocks = Vec::new();
-	let mut i = 0;
-	while i < data.len() {
-		let mut block: [u8; BLOCK_SIZE] = Default::default();
-		block.copy_from_slice(&data[i..i + BLOCK_SIZE]);
-		blocks.push(block);
-
-		i += BLOCK_SIZE;
+fn crypt_with_prepended_iv<K: AesKey>(direction: Direction, mode: Mode, data: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+	match (direction, mode) {
+		(Direction::Encrypt, Mode::Ecb) => Ok(ecb_encrypt(data, key)),
+		(Direction::Encrypt, Mode::Cbc) => Ok(cbc_encrypt(data, key)),
+		(Direction::Encrypt, Mode::Ctr) => Ok(ctr_encrypt(data, key)),
+		(Direction::Decrypt, Mode::Ecb) => ecb_decrypt(data, key),
+		(Direction::Decrypt, Mode::Cbc) => cbc_decrypt(data, key),
+		(Direction::Decrypt, Mode::Ctr) => ctr_decrypt(data, key),
 	}
-
-	blocks
 }
 
-/// Does the opposite of the group function
-fn un_group(blocks: Vec<[u8; BLOCK_SIZE]>) -> Vec<u8> {
-	blocks.concat()
+/// CBC with the IV in `iv_file`. parse_args only allows `--iv-file` with CBC.
+fn crypt_with_iv_file<K: AesKey>(
+	direction: Direction,
+	data: Vec<u8>,
+	key: K,
+	iv_file: &Path,
+	decrypted: impl Fn(Result<Vec<u8>, DecryptError>) -> Result<Vec<u8>, String>,
+) -> Result<Vec<u8>, String> {
+	match direction {
+		Direction::Encrypt => {
+			let (iv, cipher_text) = cbc_encrypt_with_generated_iv(data, key);
+			fs::write(iv_file, iv).map_err(|e| format!("could not write {}: {e}", iv_file.display()))?;
+			Ok(cipher_text)
+		}
+		Direction::Decrypt => {
+			let iv = fs::read(iv_file).map_err(|e| format!("could not read {}: {e}", iv_file.display()))?;
+			let iv: [u8; BLOCK_SIZE] = iv.as_slice().try_into().map_err(|_| {
+				format!("{} must hold exactly {BLOCK_SIZE} bytes of IV, got {}", iv_file.display(), iv.len())
+			})?;
+			decrypted(cbc_decrypt_detached(data, key, iv))
+		}
+	}
 }
 
-/// Does the opposite of the pad function.
-fn un_pad(mut data: Vec<u8>) -> Vec<u8> {
-    let number_of_bytes_to_remove = data.pop().unwrap();
-    for _ in 0..number_of_bytes_to_remove-1{
-        data.pop();
-    }
-    data
+fn key_array<const N: usize>(key: &[u8]) -> [u8; N] {
+	key.try_into().expect("key length was checked by parse_args")
 }
 
-/// The first mode we will implement is the Electronic Code Book, or ECB mode.
-/// Warning: THIS MODE IS NOT SECURE!!!!
-///
-/// This is probably the first thing you think of when considering how to encrypt
-/// large data. In this mode we simply encrypt each block of data under the same key.
-/// One good thing about this mode is that it is parallelizable. But to see why it is
-/// insecure look at: https://www.ubiqsecurity.com/wp-content/uploads/2022/02/ECB2.png
-fn ecb_encrypt(plain_text: Vec<u8>, key: [u8; 16]) -> Vec<u8> {
-	let blocks = group(pad(plain_text));
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+	let direction = match args.next().as_deref() {
+		Some("encrypt") => Direction::Encrypt,
+		Some("decrypt") => Direction::Decrypt,
+		Some(other) => return Err(format!("unknown command `{other}`")),
+		None => return Err("missing command".to_string()),
+	};
+
+	let (mut mode, mut key, mut input, mut output, mut iv_file) = (None, None, None, None, None);
+	while let Some(flag) = args.next() {
+		let value = args.next().ok_or_else(|| format!("missing value for `{flag}`"))?;
+		match flag.as_str() {
+			"--mode" => mode = Some(parse_mode(&value)?),
+			"--key" | "--key-env" | "--key-base64" if key.is_some() => return Err("give only one key option".to_string()),
+			"--key" => key = Some(parse_key(&value)?),
+			"--key-env" => key = Some(key_from_env(&value)?),
+			#[cfg(feature = "base64")]
+			"--key-base64" => key = Some(parse_key_base64(&value)?),
+			"--in" => input = Some(PathBuf::from(value)),
+			"--out" => output = Some(PathBuf::from(value)),
+			"--iv-file" => iv_file = Some(PathBuf::from(value)),
+			_ => return Err(format!("unknown option `{flag}`")),
+		}
+	}
 
-    let ciphers:Vec<[u8; BLOCK_SIZE]> = blocks.iter().map(|block| aes_encrypt(*block, &key))
-        .collect();
+	let mode = mode.ok_or("missing --mode")?;
+	if iv_file.is_some() && !matches!(mode, Mode::Cbc) {
+		return Err("--iv-file only works with --mode cbc".to_string());
+	}
 
-    un_group(ciphers)
+	Ok(Args {
+		direction,
+		mode,
+		key: key.ok_or("missing --key or --key-env")?,
+		input: input.ok_or("missing --in")?,
+		output: output.ok_or("missing --out")?,
+		iv_file,
+	})
 }
 
-/// Opposite of ecb_encrypt.
-fn ecb_decrypt(cipher_text: Vec<u8>, key: [u8; BLOCK_SIZE]) -> Vec<u8> {
-    let ciphers:Vec<[u8; BLOCK_SIZE]> = group(cipher_text);
-
-    let blocks: Vec<[u8; 16]> = ciphers.iter().map(|cipher| aes_decrypt(*cipher, &key)).collect();
-
-    un_pad(un_group(blocks))
+fn parse_mode(value: &str) -> Result<Mode, String> {
+	match value {
+		"ecb" => Ok(Mode::Ecb),
+		"cbc" => Ok(Mode::Cbc),
+		"ctr" => Ok(Mode::Ctr),
+		_ => Err(format!("unknown mode `{value}`, expected ecb, cbc or ctr")),
+	}
 }
 
-/// The next mode, which you can implement on your own is cipherblock chaining.
-/// This mode actually is secure, and it often used in real world applications.
-///
-/// In this mode, the ciphertext from the first block is XORed with the
-/// plaintext of the next block before it is encrypted.
-///
-/// For more information, and a very clear diagram,
-/// see https://de.wikipedia.org/wiki/Cipher_Block_Chaining_Mode
-///
-/// You will need to generate a random initialization vector (IV) to encrypt the
-/// very first block because it doesn't have a previous block. Typically this IV
-/// is inserted as the first block of ciphertext.
-fn cbc_encrypt(plain_text: Vec<u8>, key: [u8; BLOCK_SIZE]) -> Vec<u8> {
-	// Remember to generate a random initialization vector for the first block.
-	let blocks = group(pad(plain_text));
-
-    let mut nonce:[u8; BLOCK_SIZE] = rand::random();
-    let mut ciphers:Vec<[u8; BLOCK_SIZE]> = vec![nonce]; // inserts the IV in the first block
-
-    for i in 1..=blocks.len() {
-        ciphers[i] = aes_encrypt(xor_arrays(blocks[i], nonce), &key);
-        nonce = ciphers[i];
-    }
-
-    un_group(ciphers)
-}
+fn parse_key(value: &str) -> Result<Vec<u8>, String> {
+	let key = hex_decode(value).map_err(|_| "key must be hex".to_string())?;
+	if ![16, 24, 32].contains(&key.len()) {
+		return Err(format!("key must be 32, 48 or 64 hex characters, got {}", value.len()));
+	}
 
-fn xor_arrays(array1: [u8; BLOCK_SIZE], array2: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
-    let mut result: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
-    
-    for i in 0..BLOCK_SIZE {
-        result[i] = array1[i] ^ array2[i];
-    }
-    
-    result
+	Ok(key)
 }
 
-fn cbc_decrypt(cipher_text: Vec<u8>, key: [u8; BLOCK_SIZE]) -> Vec<u8> {
-    let mut ciphers:Vec<[u8; BLOCK_SIZE]> = group(cipher_text);
-
-    // retreive nonce and remove it
-    let mut nonce:[u8; BLOCK_SIZE] = ciphers[0];
-    ciphers.remove(0);
-
-    let mut blocks: Vec<[u8; 16]> = Vec::new();
-
-    for i in 0..ciphers.len() {
-        let block = aes_decrypt(ciphers[i], &key);
-        blocks[i] = xor_arrays(block, nonce);
-        nonce = blocks[i]
-    }
+#[cfg(feature = "base64")]
+fn parse_key_base64(value: &str) -> Result<Vec<u8>, String> {
+	let key = aes_modes::base64_decode(value).map_err(|_| "key must be base64".to_string())?;
+	if ![16, 24, 32].contains(&key.len()) {
+		return Err(format!("key must decode to 16, 24 or 32 bytes, got {}", key.len()));
+	}
 
-    // remove the
-    blocks.remove(0);
-    un_pad(un_group(blocks))
+	Ok(key)
 }
 
-/// Another mode which you can implement on your own is counter mode.
-/// This mode is secure as well, and is used in real world applications.
-/// It allows parallelized encryption and decryption, as well as random read access when decrypting.
-///
-/// In this mode, there is an index for each block being encrypted (the "counter"), as well as a random nonce.
-/// For a 128-bit cipher, the nonce is 64 bits long.
-///
-/// For the ith block, the 128-bit value V of `nonce | counter` is constructed, where | denotes
-/// concatenation. Then, V is encrypted with the key using ECB mode. Finally, the encrypted V is
-/// XOR'd with the plaintext to produce the ciphertext.
-///
-/// A very clear diagram is present here:
-/// https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#Counter_(CTR)
-///
-/// Once again, you will need to generate a random nonce which is 64 bits long. This should be
-/// inserted as the first block of the ciphertext.
-fn ctr_encrypt(plain_text: Vec<u8>, key: [u8; BLOCK_SIZE]) -> Vec<u8> {
-	// Remember to generate a random nonce
-
-	let blocks = group(pad(plain_text));
-
-    let nonce:[u8; BLOCK_SIZE] = rand::random();
-    let mut counter: [u8; 8] = [0; 8];
+/// Reads the hex key from the environment variable `name`. The variable's value is wiped
+/// from our copy of it once parsed.
+fn key_from_env(name: &str) -> Result<Vec<u8>, String> {
+	let value = std::env::var(name).map_err(|e| match e {
+		VarError::NotPresent => format!("environment variable `{name}` is not set"),
+		VarError::NotUnicode(_) => format!("environment variable `{name}`: key must be hex"),
+	})?;
 
-    let mut ciphers:Vec<[u8; BLOCK_SIZE]> = vec![nonce]; // adding 128 bit nonce in the front
-    for i in 1..=blocks.len() {
-        let encypted_v = aes_encrypt( // encrypt V
-            concat_arrays(nonce, counter),
-                  &key
-              );
+	let key = parse_key(&value).map_err(|message| format!("environment variable `{name}`: {message}"));
+	wipe(value);
 
-        ciphers[i] = xor_arrays( // xor block with encrypted V
-            blocks[i], 
-            encypted_v
-        );
-        increment_counter(&mut counter);
-    }
-
-    un_group(ciphers)
-}
-
-fn increment_counter(counter: &mut [u8; 8]) {
-    for i in (0..8).rev() {
-        if counter[i] == u8::MAX {
-            counter[i] = 0;
-        } else {
-            counter[i] += 1;
-            break;
-        }
-    }
+	key
 }
 
-fn concat_arrays(arr1: [u8; BLOCK_SIZE], arr2: [u8; BLOCK_SIZE/2]) -> [u8; 16] {
-    let mut result = [0u8; BLOCK_SIZE]; // Create an array to hold the result
-    
-    // make sure to use only first 64 bits in nonce
-    for i in 0..BLOCK_SIZE/2 {
-        result[i] = arr1[i];
-    }
-    
-    // Copy elements from counter
-    for i in 0..BLOCK_SIZE/2 {
-        result[i + 8] = arr2[i];
-    }
-    
-    result
+/// Overwrites `secret` with zeros before freeing it. The black_box stops the compiler from
+/// removing the writes as dead stores.
+fn wipe(secret: String) {
+	let mut bytes = secret.into_bytes();
+	bytes.fill(0);
+	std::hint::black_box(&bytes);
 }
-
-fn ctr_decrypt(cipher_text: Vec<u8>, key: [u8; BLOCK_SIZE]) -> Vec<u8> {
-    let mut ciphers:Vec<[u8; BLOCK_SIZE]> = group(cipher_text);
-
-    // retreive nonce
-    let nonce:[u8; BLOCK_SIZE] = ciphers[0];
-    ciphers.remove(0);
-
-    let mut counter: [u8; 8] = [0; 8];
-    let mut blocks: Vec<[u8; 16]> = Vec::new();
-
-    for i in 0..ciphers.len() {
-        // decrypt v
-        let v: [u8; 16] = aes_decrypt(concat_arrays(nonce, counter), &key);
-        blocks[i] = xor_arrays(ciphers[i], v);
-        increment_counter(&mut counter)
-    }
-
-    un_pad(un_group(blocks))
-}
\ No newline at end of file
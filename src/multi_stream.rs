@@ -0,0 +1,92 @@
+//! Several independent CTR streams under one key, for transports that multiplex logical
+//! channels over one connection.
+//!
+//! Every message gets the CTR nonce `channel_id || message_number`, both as 32-bit
+//! big-endian integers, where the message number counts up from 0 separately on each
+//! channel. Two messages can only share a nonce if they share both the channel and the
+//! message number, which one MultiStreamCipher never hands out twice. So there are no
+//! collisions to bound, as long as:
+//!
+//! - Each key has only one MultiStreamCipher encrypting under it. A second one, or the same
+//!   channels after a restart, starts counting from 0 again and reuses every nonce.
+//! - Nothing else encrypts under the key. ctr_encrypt's random nonces come from the same 64
+//!   bits and could land on one of these.
+//! - No channel sends more than 2^32 messages. encrypt panics rather than wrap around.
+
+use std::collections::HashMap;
+
+use crate::{ctr_apply_keystream, AesKey, CounterLayout, DecryptError, BLOCK_SIZE};
+
+/// The length of the message number at the front of each ciphertext.
+const MESSAGE_NUMBER_SIZE: usize = 4;
+
+/// Encrypts messages on many channels under one key with CTR, keeping a message counter
+/// per channel. See the module docs for how the nonces are built.
+///
+/// The ciphertext is the message number as 4 big-endian bytes, then the CTR ciphertext,
+/// so it is 4 bytes longer than the plaintext. The channel id isn't included: the receiver
+/// knows which channel a message arrived on and passes it to decrypt.
+pub struct MultiStreamCipher<K: AesKey> {
+	cipher: K::Cipher,
+	next_message: HashMap<u32, u32>,
+}
+
+impl<K: AesKey> MultiStreamCipher<K> {
+	/// Expands `key` once, for every message on every channel.
+	pub fn new(key: K) -> Self {
+		MultiStreamCipher { cipher: key.cipher(), next_message: HashMap::new() }
+	}
+
+	/// Encrypts `plain_text` as the next message on `channel_id`.
+	///
+	/// # Panics
+	///
+	/// If `channel_id` has already sent 2^32 messages under this key.
+	pub fn encrypt(&mut self, channel_id: u32, plain_text: Vec<u8>) -> Vec<u8> {
+		mode_span!("multi_stream_encrypt", "ctr", plain_text.len(), plain_text.len().div_ceil(BLOCK_SIZE));
+
+		let next = self.next_message.entry(channel_id).or_insert(0);
+		let message_number = *next;
+		*next = next.checked_add(1).unwrap_or_else(|| panic!("channel {channel_id} has used all 2^32 message numbers"));
+
+		let mut cipher_text = Vec::with_capacity(MESSAGE_NUMBER_SIZE + plain_text.len());
+		cipher_text.extend_from_slice(&message_number.to_be_bytes());
+		cipher_text.extend_from_slice(&plain_text);
+
+		let nonce = channel_nonce(channel_id, message_number);
+		ctr_apply_keystream(&mut cipher_text[MESSAGE_NUMBER_SIZE..], &self.cipher, nonce, CounterLayout::default());
+		cipher_text
+	}
+
+	/// Opposite of encrypt, for a message that arrived on `channel_id`.
+	///
+	/// Like ctr_decrypt, nothing is authenticated: the wrong channel id, or a modified
+	/// ciphertext, decrypts to garbage without an error.
+	pub fn decrypt(&self, channel_id: u32, mut cipher_text: Vec<u8>) -> Result<Vec<u8>, DecryptError> {
+		mode_span!(
+			"multi_stream_decrypt",
+			"ctr",
+			cipher_text.len(),
+			cipher_text.len().saturating_sub(MESSAGE_NUMBER_SIZE).div_ceil(BLOCK_SIZE)
+		);
+
+		if cipher_text.is_empty() {
+			return Err(DecryptError::Empty);
+		}
+		if cipher_text.len() < MESSAGE_NUMBER_SIZE {
+			return Err(DecryptError::InvalidLength);
+		}
+
+		let (message_number, data) = cipher_text.split_at_mut(MESSAGE_NUMBER_SIZE);
+		let message_number = u32::from_be_bytes((&*message_number).try_into().expect("split off exactly the message number"));
+
+		ctr_apply_keystream(data, &self.cipher, channel_nonce(channel_id, message_number), CounterLayout::default());
+		cipher_text.drain(..MESSAGE_NUMBER_SIZE);
+		Ok(cipher_text)
+	}
+}
+
+/// `channel_id || message_number`, the CTR nonce for one message.
+fn channel_nonce(channel_id: u32, message_number: u32) -> [u8; BLOCK_SIZE / 2] {
+	((u64::from(channel_id) << 32) | u64::from(message_number)).to_be_bytes()
+}
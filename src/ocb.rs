@@ -0,0 +1,233 @@
+//! AES-OCB3 authenticated encryption, as specified in RFC 7253.
+//!
+//! OCB encrypts and authenticates in a single pass. Each block is encrypted with ECB under a
+//! per-block offset, so unlike CBC it is parallelizable. A checksum of the plaintext is then
+//! encrypted into the tag. The offsets come from a table of key-derived values L_i: block i
+//! uses L_{ntz(i)}, where ntz is the number of trailing zero bits of i. Consecutive offsets
+//! therefore differ by one cheap XOR.
+//!
+//! Associated data is authenticated but not encrypted, using the same offset construction.
+//! We always use a 96-bit nonce and the full 128-bit tag. The nonce must never repeat
+//! under the same key.
+
+use aes::cipher::{consts::U16, BlockDecrypt, BlockEncrypt};
+
+use crate::{aes_decrypt_block, aes_encrypt_block, ct_ciphertext_eq, encode_aad_list, AesKey, DecryptError, BLOCK_SIZE};
+
+/// The length of an OCB nonce. RFC 7253 allows up to 120 bits, but recommends 96.
+pub const OCB_NONCE_SIZE: usize = 12;
+
+/// The length of the authentication tag appended to the ciphertext.
+pub const OCB_TAG_SIZE: usize = 16;
+
+/// Encrypts and authenticates `plain_text`, and authenticates (but doesn't encrypt)
+/// `associated_data`. The result is `ciphertext || tag`, exactly OCB_TAG_SIZE bytes longer
+/// than the plaintext, since OCB needs no padding.
+pub fn ocb_encrypt<K: AesKey>(
+	key: K,
+	nonce: &[u8; OCB_NONCE_SIZE],
+	associated_data: &[u8],
+	plain_text: &[u8],
+) -> Vec<u8> {
+	mode_span!("ocb_encrypt", "ocb", plain_text.len(), plain_text.len().div_ceil(BLOCK_SIZE));
+	let ocb = Ocb::new(key);
+	let mut offset = ocb.initial_offset(nonce);
+	let mut checksum = 0u128;
+	let mut cipher_text = Vec::with_capacity(plain_text.len() + OCB_TAG_SIZE);
+
+	let mut chunks = plain_text.chunks_exact(BLOCK_SIZE);
+	for (i, block) in (1..).zip(&mut chunks) {
+		let block = to_block(block);
+		offset ^= ocb.l(ntz(i));
+		cipher_text.extend_from_slice(&(ocb.encipher(block ^ offset) ^ offset).to_be_bytes());
+		checksum ^= block;
+	}
+
+	let last = chunks.remainder();
+	if !last.is_empty() {
+		offset ^= ocb.l_star;
+		let pad = ocb.encipher(offset).to_be_bytes();
+		cipher_text.extend(last.iter().zip(pad).map(|(p, k)| p ^ k));
+		checksum ^= pad_partial(last);
+	}
+
+	let tag = ocb.encipher(checksum ^ offset ^ ocb.l_dollar) ^ ocb.hash(associated_data);
+	cipher_text.extend_from_slice(&tag.to_be_bytes());
+
+	cipher_text
+}
+
+/// Opposite of ocb_encrypt.
+///
+/// Returns `AuthenticationFailed` if the tag doesn't verify, which means the ciphertext,
+/// the associated data, the nonce or the key is not what was used to encrypt. No
+/// plaintext is released in that case.
+pub fn ocb_decrypt<K: AesKey>(
+	key: K,
+	nonce: &[u8; OCB_NONCE_SIZE],
+	associated_data: &[u8],
+	cipher_text: &[u8],
+) -> Result<Vec<u8>, DecryptError> {
+	mode_span!("ocb_decrypt", "ocb", cipher_text.len(), cipher_text.len().saturating_sub(OCB_TAG_SIZE).div_ceil(BLOCK_SIZE));
+	if cipher_text.len() < OCB_TAG_SIZE {
+		return Err(DecryptError::InvalidLength);
+	}
+	let (body, tag) = cipher_text.split_at(cipher_text.len() - OCB_TAG_SIZE);
+
+	let ocb = Ocb::new(key);
+	let mut offset = ocb.initial_offset(nonce);
+	let mut checksum = 0u128;
+	let mut plain_text = Vec::with_capacity(body.len());
+
+	let mut chunks = body.chunks_exact(BLOCK_SIZE);
+	for (i, block) in (1..).zip(&mut chunks) {
+		offset ^= ocb.l(ntz(i));
+		let block = ocb.decipher(to_block(block) ^ offset) ^ offset;
+		plain_text.extend_from_slice(&block.to_be_bytes());
+		checksum ^= block;
+	}
+
+	let last = chunks.remainder();
+	if !last.is_empty() {
+		offset ^= ocb.l_star;
+		let pad = ocb.encipher(offset).to_be_bytes();
+		let start = plain_text.len();
+		plain_text.extend(last.iter().zip(pad).map(|(c, k)| c ^ k));
+		checksum ^= pad_partial(&plain_text[start..]);
+	}
+
+	let expected = ocb.encipher(checksum ^ offset ^ ocb.l_dollar) ^ ocb.hash(associated_data);
+
+	// Compared without exiting early, so timing doesn't reveal how much of the tag was right.
+	if !ct_ciphertext_eq(&expected.to_be_bytes(), tag) {
+		return Err(DecryptError::AuthenticationFailed);
+	}
+
+	Ok(plain_text)
+}
+
+/// Same as ocb_encrypt, but authenticates a list of associated data fields, encoded with
+/// encode_aad_list so that their boundaries are authenticated too.
+pub fn ocb_encrypt_with_aad_list<K: AesKey>(
+	key: K,
+	nonce: &[u8; OCB_NONCE_SIZE],
+	associated_data: &[&[u8]],
+	plain_text: &[u8],
+) -> Vec<u8> {
+	ocb_encrypt(key, nonce, &encode_aad_list(associated_data), plain_text)
+}
+
+/// Opposite of ocb_encrypt_with_aad_list. The fields must be the same, and split the same
+/// way, as when encrypting.
+pub fn ocb_decrypt_with_aad_list<K: AesKey>(
+	key: K,
+	nonce: &[u8; OCB_NONCE_SIZE],
+	associated_data: &[&[u8]],
+	cipher_text: &[u8],
+) -> Result<Vec<u8>, DecryptError> {
+	ocb_decrypt(key, nonce, &encode_aad_list(associated_data), cipher_text)
+}
+
+/// The expanded key together with the key-dependent values every OCB operation uses.
+/// Blocks are handled as big-endian u128s so the offset and doubling arithmetic is plain
+/// integer XOR and shifting.
+struct Ocb<C> {
+	cipher: C,
+	/// L_* = E(K, 0^128)
+	l_star: u128,
+	/// L_$ = double(L_*)
+	l_dollar: u128,
+	/// L_0 = double(L_$)
+	l_0: u128,
+}
+
+impl<C: BlockEncrypt<BlockSize = U16> + BlockDecrypt<BlockSize = U16>> Ocb<C> {
+	fn new<K: AesKey<Cipher = C>>(key: K) -> Self {
+		let cipher = key.cipher();
+		let l_star = u128::from_be_bytes(aes_encrypt_block(&cipher, [0; BLOCK_SIZE]));
+		let l_dollar = double(l_star);
+		let l_0 = double(l_dollar);
+
+		Ocb { cipher, l_star, l_dollar, l_0 }
+	}
+
+	fn encipher(&self, block: u128) -> u128 {
+		u128::from_be_bytes(aes_encrypt_block(&self.cipher, block.to_be_bytes()))
+	}
+
+	fn decipher(&self, block: u128) -> u128 {
+		u128::from_be_bytes(aes_decrypt_block(&self.cipher, block.to_be_bytes()))
+	}
+
+	/// L_i = double(L_{i-1}). `i` is at most 63, so computing it fresh costs a few shifts.
+	fn l(&self, i: u32) -> u128 {
+		(0..i).fold(self.l_0, |l, _| double(l))
+	}
+
+	/// Offset_0, from the nonce (RFC 7253 section 4.2).
+	fn initial_offset(&self, nonce: &[u8; OCB_NONCE_SIZE]) -> u128 {
+		// Nonce = num2str(TAGLEN mod 128, 7) || zeros(120 - bitlen(N)) || 1 || N. With a full
+		// tag and a 96-bit nonce that is 31 zero bits, a one bit, then N.
+		let mut nonce_block = [0u8; BLOCK_SIZE];
+		nonce_block[BLOCK_SIZE - OCB_NONCE_SIZE - 1] = 1;
+		nonce_block[BLOCK_SIZE - OCB_NONCE_SIZE..].copy_from_slice(nonce);
+		let nonce_block = u128::from_be_bytes(nonce_block);
+
+		let bottom = (nonce_block & 0x3f) as u32;
+		let ktop = self.encipher(nonce_block & !0x3f);
+
+		// Stretch = Ktop || (Ktop[1..64] xor Ktop[9..72]), and Offset_0 is the 128 bits of
+		// Stretch starting after the first `bottom` bits.
+		let stretch_tail = ((ktop >> 64) ^ ((ktop << 8) >> 64)) as u64;
+		if bottom == 0 {
+			ktop
+		} else {
+			(ktop << bottom) | (u128::from(stretch_tail) >> (64 - bottom))
+		}
+	}
+
+	/// HASH(K, A): the authentication of the associated data (RFC 7253 section 4.1).
+	fn hash(&self, associated_data: &[u8]) -> u128 {
+		let mut offset = 0u128;
+		let mut sum = 0u128;
+
+		let mut chunks = associated_data.chunks_exact(BLOCK_SIZE);
+		for (i, block) in (1..).zip(&mut chunks) {
+			offset ^= self.l(ntz(i));
+			sum ^= self.encipher(to_block(block) ^ offset);
+		}
+
+		let last = chunks.remainder();
+		if !last.is_empty() {
+			offset ^= self.l_star;
+			sum ^= self.encipher(pad_partial(last) ^ offset);
+		}
+
+		sum
+	}
+}
+
+/// Multiplies by x in GF(2^128): shift left one bit, and fold the carry back in with the
+/// reduction polynomial x^128 + x^7 + x^2 + x + 1.
+fn double(block: u128) -> u128 {
+	(block << 1) ^ if block >> 127 == 1 { 0x87 } else { 0 }
+}
+
+/// The number of trailing zero bits of the (1-based) block index.
+fn ntz(i: u64) -> u32 {
+	i.trailing_zeros()
+}
+
+fn to_block(bytes: &[u8]) -> u128 {
+	let mut block = [0u8; BLOCK_SIZE];
+	block.copy_from_slice(bytes);
+	u128::from_be_bytes(block)
+}
+
+/// Pads a final partial block as `bytes || 1 || 0...`.
+fn pad_partial(bytes: &[u8]) -> u128 {
+	let mut block = [0u8; BLOCK_SIZE];
+	block[..bytes.len()].copy_from_slice(bytes);
+	block[bytes.len()] = 0x80;
+	u128::from_be_bytes(block)
+}
@@ -0,0 +1,375 @@
+//! Streaming versions of the encrypt functions, for input too large to hold in memory.
+//!
+//! The input is read one block at a time and each ciphertext block is written as soon as it
+//! is ready. Only the final, partial block (possibly empty) is held back until the reader
+//! reports end of stream, since that's the only block that gets padded, or for CTR cut
+//! short. The output is the same as the in-memory function's, IV or nonce included, so it
+//! decrypts with ecb_decrypt, cbc_decrypt or ctr_decrypt.
+//!
+//! EncryptedWriter and EncryptedReader do the same for CBC and CTR as `Write`/`Read`
+//! adapters, so the data can be pushed through with `io::copy` or any other IO code instead
+//! of a single call.
+
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+
+use aes::cipher::{consts::U16, BlockDecrypt, BlockEncrypt};
+
+use crate::{
+	aes_decrypt_block, aes_encrypt_block, counter_to_bytes, group_padded, increment_counter, pad, random_iv, un_pad,
+	xor_arrays, AesKey, CounterLayout, DecryptError, BLOCK_SIZE,
+};
+
+/// Streaming ecb_encrypt. Everything that makes ECB insecure applies here too.
+pub fn ecb_encrypt_stream<R: Read, W: Write, K: AesKey>(reader: R, writer: W, key: K) -> io::Result<()> {
+	mode_span!("ecb_encrypt_stream", "ecb");
+	let aes = key.cipher();
+
+	encrypt_blocks(reader, writer, &[], Last::Padded, |block| aes_encrypt_block(&aes, block))
+}
+
+/// Streaming cbc_encrypt, with a random IV written before the first block.
+pub fn cbc_encrypt_stream<R: Read, W: Write, K: AesKey>(reader: R, writer: W, key: K) -> io::Result<()> {
+	mode_span!("cbc_encrypt_stream", "cbc");
+	let aes = key.cipher();
+	let iv: [u8; BLOCK_SIZE] = random_iv();
+	let mut previous = iv;
+
+	encrypt_blocks(reader, writer, &iv, Last::Padded, |block| {
+		previous = aes_encrypt_block(&aes, xor_arrays(block, previous));
+		previous
+	})
+}
+
+/// Streaming ctr_encrypt, with a random 64-bit nonce written before the first block.
+pub fn ctr_encrypt_stream<R: Read, W: Write, K: AesKey>(reader: R, writer: W, key: K) -> io::Result<()> {
+	mode_span!("ctr_encrypt_stream", "ctr");
+	let aes = key.cipher();
+	let nonce: [u8; BLOCK_SIZE / 2] = random_iv();
+	let layout = CounterLayout::default();
+	let mut counter: u64 = 0;
+
+	encrypt_blocks(reader, writer, &nonce, Last::Truncated, |block| {
+		let v = aes_encrypt_block(&aes, layout.counter_block(nonce, counter_to_bytes(counter)));
+		counter = increment_counter(counter);
+		xor_arrays(block, v)
+	})
+}
+
+/// What happens to the partial block left at the end of the stream.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Last {
+	/// Padded out to a whole block, a whole block of padding if nothing is left.
+	Padded,
+	/// Encrypted as is, for CTR, which only XORs a keystream over the data. The output is
+	/// cut to the same length, so nothing at all is written if nothing is left.
+	Truncated,
+}
+
+/// Writes `header`, then every block of `reader` passed through `encrypt_block`, with the
+/// last one handled as `last` says.
+///
+/// Both ends are buffered here, so callers don't need to wrap files in BufReader/BufWriter
+/// to avoid a system call per block. The input length and the number of blocks written are
+/// recorded on the caller's tracing span once the input runs out.
+fn encrypt_blocks<R: Read, W: Write>(
+	reader: R,
+	writer: W,
+	header: &[u8],
+	last: Last,
+	mut encrypt_block: impl FnMut([u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE],
+) -> io::Result<()> {
+	let mut reader = BufReader::new(reader);
+	let mut writer = BufWriter::new(writer);
+	let mut block = [0u8; BLOCK_SIZE];
+	#[cfg(feature = "tracing")]
+	let mut input_len = 0;
+
+	writer.write_all(header)?;
+
+	loop {
+		let filled = read_block(&mut reader, &mut block)?;
+		#[cfg(feature = "tracing")]
+		{
+			input_len += filled;
+		}
+		if filled < BLOCK_SIZE {
+			#[cfg(feature = "tracing")]
+			tracing::Span::current().record("input_len", input_len).record(
+				"blocks",
+				match last {
+					Last::Padded => input_len / BLOCK_SIZE + 1,
+					Last::Truncated => input_len.div_ceil(BLOCK_SIZE),
+				},
+			);
+			// End of stream. When padding, what's left pads out to exactly one more block.
+			match last {
+				Last::Padded => {
+					for padded in group_padded(pad(block[..filled].to_vec())) {
+						writer.write_all(&encrypt_block(padded))?;
+					}
+				}
+				Last::Truncated if filled > 0 => {
+					block[filled..].fill(0);
+					writer.write_all(&encrypt_block(block)[..filled])?;
+				}
+				Last::Truncated => {}
+			}
+			return writer.flush();
+		}
+		writer.write_all(&encrypt_block(block))?;
+	}
+}
+
+/// Reads until `block` is full or the stream ends, and returns how many bytes were read.
+/// A short read from the reader doesn't mean the stream has ended, only a read of 0 does.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8]) -> io::Result<usize> {
+	let mut filled = 0;
+
+	while filled < block.len() {
+		match reader.read(&mut block[filled..]) {
+			Ok(0) => break,
+			Ok(n) => filled += n,
+			Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+			Err(e) => return Err(e),
+		}
+	}
+
+	Ok(filled)
+}
+
+/// A `Write` adapter that encrypts everything written to it with CBC or CTR.
+///
+/// The IV or nonce is written as soon as the writer is created, and each whole block as
+/// soon as it has been written, so at most one partial block is held back. Call finish once
+/// everything has been written: it encrypts that last block, padded for CBC, and returns the
+/// inner writer. Dropping the writer without calling finish loses the last block, and the
+/// output won't decrypt.
+///
+/// The output is the same as cbc_encrypt's or ctr_encrypt's, so it decrypts with
+/// cbc_decrypt, ctr_decrypt or an EncryptedReader.
+pub struct EncryptedWriter<W: Write, K: AesKey> {
+	writer: BufWriter<W>,
+	cipher: K::Cipher,
+	chain: Chain,
+	/// Bytes written since the last whole block, always fewer than BLOCK_SIZE.
+	pending: Vec<u8>,
+}
+
+impl<W: Write, K: AesKey> EncryptedWriter<W, K> {
+	/// Starts a CBC stream, writing a random IV to `writer` before anything else.
+	pub fn cbc(writer: W, key: K) -> io::Result<Self> {
+		let iv: [u8; BLOCK_SIZE] = random_iv();
+		Self::start(writer, key, &iv, Chain::Cbc { previous: iv })
+	}
+
+	/// Starts a CTR stream, writing a random 64-bit nonce to `writer` before anything else.
+	pub fn ctr(writer: W, key: K) -> io::Result<Self> {
+		let nonce: [u8; BLOCK_SIZE / 2] = random_iv();
+		Self::start(writer, key, &nonce, Chain::Ctr { nonce, counter: 0 })
+	}
+
+	fn start(writer: W, key: K, header: &[u8], chain: Chain) -> io::Result<Self> {
+		let mut writer = BufWriter::new(writer);
+		writer.write_all(header)?;
+
+		Ok(EncryptedWriter { writer, cipher: key.cipher(), chain, pending: Vec::with_capacity(BLOCK_SIZE) })
+	}
+
+	/// Encrypts the last partial block, padded for CBC, flushes everything and returns the
+	/// inner writer.
+	pub fn finish(mut self) -> io::Result<W> {
+		let filled = self.pending.len();
+		match self.chain {
+			Chain::Cbc { .. } => {
+				for padded in group_padded(pad(std::mem::take(&mut self.pending))) {
+					self.writer.write_all(&self.chain.encrypt(&self.cipher, padded))?;
+				}
+			}
+			Chain::Ctr { .. } if filled > 0 => {
+				let mut block = [0u8; BLOCK_SIZE];
+				block[..filled].copy_from_slice(&self.pending);
+				self.writer.write_all(&self.chain.encrypt(&self.cipher, block)[..filled])?;
+			}
+			Chain::Ctr { .. } => {}
+		}
+
+		self.writer.into_inner().map_err(io::IntoInnerError::into_error)
+	}
+}
+
+impl<W: Write, K: AesKey> Write for EncryptedWriter<W, K> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.pending.extend_from_slice(buf);
+
+		let whole = self.pending.len() / BLOCK_SIZE * BLOCK_SIZE;
+		for block in self.pending[..whole].chunks_exact(BLOCK_SIZE) {
+			let block = block.try_into().expect("chunks are a whole block");
+			self.writer.write_all(&self.chain.encrypt(&self.cipher, block))?;
+		}
+		self.pending.drain(..whole);
+
+		Ok(buf.len())
+	}
+
+	/// Flushes the blocks encrypted so far. The partial block held back until finish can't
+	/// be.
+	fn flush(&mut self) -> io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+/// A `Read` adapter that decrypts a CBC or CTR ciphertext as it is read, reading the IV or
+/// nonce first.
+///
+/// For CBC the last block holds the padding, so every block is held back until the next one
+/// arrives, or the stream ends and it can be unpadded. Anything cbc_decrypt or ctr_decrypt
+/// would reject comes out of `read` as an `InvalidData` error wrapping the DecryptError.
+/// Plaintext from earlier blocks may already have been read by then, so only trust the
+/// output once `read` has returned 0.
+pub struct EncryptedReader<R: Read, K: AesKey> {
+	reader: BufReader<R>,
+	cipher: K::Cipher,
+	state: ReadState,
+	/// Decrypted bytes not yet read, from `position` on.
+	plain_text: Vec<u8>,
+	position: usize,
+}
+
+/// How far an EncryptedReader has got.
+enum ReadState {
+	/// The header for this mode hasn't been read yet.
+	CbcHeader,
+	CtrHeader,
+	/// In the middle of the blocks. `held` is the CBC block decrypted last, not released yet.
+	Blocks { chain: Chain, held: Option<[u8; BLOCK_SIZE]> },
+	/// The stream has ended and everything has been decrypted.
+	Done,
+}
+
+impl<R: Read, K: AesKey> EncryptedReader<R, K> {
+	/// Decrypts a CBC stream, `IV || ciphertext` as cbc_encrypt produces it.
+	pub fn cbc(reader: R, key: K) -> Self {
+		Self::start(reader, key, ReadState::CbcHeader)
+	}
+
+	/// Decrypts a CTR stream, `nonce || ciphertext` as ctr_encrypt produces it.
+	pub fn ctr(reader: R, key: K) -> Self {
+		Self::start(reader, key, ReadState::CtrHeader)
+	}
+
+	fn start(reader: R, key: K, state: ReadState) -> Self {
+		EncryptedReader { reader: BufReader::new(reader), cipher: key.cipher(), state, plain_text: Vec::new(), position: 0 }
+	}
+
+	/// Reads and decrypts the next piece of the stream into `plain_text`, which may stay
+	/// empty, e.g. after the header or the first CBC block.
+	fn fill(&mut self) -> io::Result<()> {
+		let invalid = |e: DecryptError| io::Error::new(ErrorKind::InvalidData, e);
+		let mut block = [0u8; BLOCK_SIZE];
+
+		match &mut self.state {
+			ReadState::CbcHeader => {
+				match read_block(&mut self.reader, &mut block)? {
+					0 => return Err(invalid(DecryptError::Empty)),
+					BLOCK_SIZE => {}
+					_ => return Err(invalid(DecryptError::InvalidLength)),
+				}
+				self.state = ReadState::Blocks { chain: Chain::Cbc { previous: block }, held: None };
+			}
+			ReadState::CtrHeader => {
+				let mut nonce = [0u8; BLOCK_SIZE / 2];
+				match read_block(&mut self.reader, &mut nonce)? {
+					0 => return Err(invalid(DecryptError::Empty)),
+					n if n == nonce.len() => {}
+					_ => return Err(invalid(DecryptError::InvalidLength)),
+				}
+				self.state = ReadState::Blocks { chain: Chain::Ctr { nonce, counter: 0 }, held: None };
+			}
+			ReadState::Blocks { chain: chain @ Chain::Cbc { .. }, held } => {
+				match read_block(&mut self.reader, &mut block)? {
+					BLOCK_SIZE => {
+						if let Some(previous) = held.replace(chain.decrypt(&self.cipher, block)) {
+							self.plain_text.extend_from_slice(&previous);
+						}
+					}
+					0 => {
+						let last = held.ok_or(invalid(DecryptError::Empty))?;
+						self.plain_text = un_pad(last.to_vec()).map_err(invalid)?;
+						self.state = ReadState::Done;
+					}
+					_ => return Err(invalid(DecryptError::InvalidLength)),
+				}
+			}
+			ReadState::Blocks { chain: chain @ Chain::Ctr { .. }, .. } => {
+				let filled = read_block(&mut self.reader, &mut block)?;
+				if filled > 0 {
+					self.plain_text.extend_from_slice(&chain.decrypt(&self.cipher, block)[..filled]);
+				}
+				if filled < BLOCK_SIZE {
+					self.state = ReadState::Done;
+				}
+			}
+			ReadState::Done => {}
+		}
+
+		Ok(())
+	}
+}
+
+impl<R: Read, K: AesKey> Read for EncryptedReader<R, K> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		while self.position == self.plain_text.len() {
+			if let ReadState::Done = self.state {
+				return Ok(0);
+			}
+			self.plain_text.clear();
+			self.position = 0;
+			self.fill()?;
+		}
+
+		let n = buf.len().min(self.plain_text.len() - self.position);
+		buf[..n].copy_from_slice(&self.plain_text[self.position..self.position + n]);
+		self.position += n;
+
+		Ok(n)
+	}
+}
+
+/// The chaining state CBC and CTR carry from one block to the next.
+enum Chain {
+	/// The previous ciphertext block, the IV to begin with.
+	Cbc { previous: [u8; BLOCK_SIZE] },
+	/// The nonce and the counter for the next block.
+	Ctr { nonce: [u8; BLOCK_SIZE / 2], counter: u64 },
+}
+
+impl Chain {
+	fn encrypt<C: BlockEncrypt<BlockSize = U16>>(&mut self, cipher: &C, block: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+		match self {
+			Chain::Cbc { previous } => {
+				*previous = aes_encrypt_block(cipher, xor_arrays(block, *previous));
+				*previous
+			}
+			Chain::Ctr { .. } => self.keystream(cipher, block),
+		}
+	}
+
+	fn decrypt<C: BlockEncrypt<BlockSize = U16> + BlockDecrypt<BlockSize = U16>>(
+		&mut self,
+		cipher: &C,
+		block: [u8; BLOCK_SIZE],
+	) -> [u8; BLOCK_SIZE] {
+		match self {
+			Chain::Cbc { previous } => xor_arrays(aes_decrypt_block(cipher, block), std::mem::replace(previous, block)),
+			Chain::Ctr { .. } => self.keystream(cipher, block),
+		}
+	}
+
+	/// XORs `block` with the next CTR keystream block. Only called for Ctr.
+	fn keystream<C: BlockEncrypt<BlockSize = U16>>(&mut self, cipher: &C, block: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+		let Chain::Ctr { nonce, counter } = self else { unreachable!("keystream is only used for CTR") };
+		let v = aes_encrypt_block(cipher, CounterLayout::default().counter_block(*nonce, counter_to_bytes(*counter)));
+		*counter = increment_counter(*counter);
+		xor_arrays(block, v)
+	}
+}
@@ -0,0 +1,55 @@
+//! Test helpers for code built on this crate, behind the `test-util` feature.
+//!
+//! A downstream crate implementing its own BlockMode enables the feature for its tests only:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! aes-mode-activity = { version = "0.1", features = ["test-util"] }
+//! ```
+//!
+//! and then checks its mode like this:
+//!
+//! ```
+//! use aes_modes::{assert_roundtrip, cbc_decrypt, cbc_encrypt, AesKey, BlockMode, DecryptError};
+//!
+//! /// CBC with the message length prepended, as an example of a mode built on top.
+//! struct LengthPrefixedCbc;
+//!
+//! impl<K: AesKey> BlockMode<K> for LengthPrefixedCbc {
+//!     fn encrypt(&self, plain_text: Vec<u8>, key: K) -> Vec<u8> {
+//!         let mut framed = (plain_text.len() as u64).to_be_bytes().to_vec();
+//!         framed.extend(plain_text);
+//!         cbc_encrypt(framed, key)
+//!     }
+//!
+//!     fn decrypt(&self, cipher_text: Vec<u8>, key: K) -> Result<Vec<u8>, DecryptError> {
+//!         let mut framed = cbc_decrypt(cipher_text, key)?;
+//!         if framed.len() < 8 {
+//!             return Err(DecryptError::InvalidLength);
+//!         }
+//!         Ok(framed.split_off(8))
+//!     }
+//! }
+//!
+//! assert_roundtrip(&LengthPrefixedCbc, *b"YELLOW SUBMARINE", &[b"hello world"]);
+//! ```
+
+use crate::{AesKey, BlockMode, BLOCK_SIZE};
+
+/// The message lengths every round trip is checked at: empty, the bytes either side of a
+/// block boundary, where the padding and counter bugs show up, and one longer message.
+const EDGE_CASE_LENGTHS: [usize; 6] = [0, 1, BLOCK_SIZE - 1, BLOCK_SIZE, BLOCK_SIZE + 1, 4096];
+
+/// Encrypts and decrypts a message of each edge-case length, then each of `samples`, with
+/// `mode` under `key`, and panics naming the message if one doesn't come back unchanged.
+pub fn assert_roundtrip<K: AesKey + Copy, M: BlockMode<K> + ?Sized>(mode: &M, key: K, samples: &[&[u8]]) {
+	let edge_cases = EDGE_CASE_LENGTHS.map(|len| (0..len).map(|i| i as u8).collect::<Vec<u8>>());
+
+	for message in edge_cases.iter().map(Vec::as_slice).chain(samples.iter().copied()) {
+		let cipher_text = mode.encrypt(message.to_vec(), key);
+		match mode.decrypt(cipher_text, key) {
+			Ok(plain_text) => assert_eq!(plain_text, message, "{}-byte message changed in the round trip", message.len()),
+			Err(e) => panic!("{}-byte message failed to decrypt: {e}", message.len()),
+		}
+	}
+}
@@ -0,0 +1,135 @@
+//! The mode functions size their buffers up front, so the number of allocations they make
+//! doesn't grow with the input.
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	cell::Cell,
+};
+
+use aes_modes::{
+	cbc_decrypt, cbc_decrypt_bounded, cbc_encrypt, ctr_decrypt, ctr_decrypt_bounded, ctr_decrypt_in_place, ctr_encrypt,
+	ecb_decrypt, ecb_decrypt_bounded, ecb_encrypt, gcm_encrypt, gcm_verify, group, pad, DecryptError, BLOCK_SIZE,
+};
+
+/// Counts allocations and reallocations made by the current thread, so tests running in
+/// parallel don't see each other's.
+struct Counting;
+
+thread_local! {
+	static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+	// Ignore allocations made while the thread-local is being torn down.
+	let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		count();
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		count();
+		System.realloc(ptr, layout, new_size)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+/// How many times `f` allocates or reallocates. The input is built before counting starts.
+fn allocations<T>(input: Vec<u8>, f: impl FnOnce(Vec<u8>) -> T) -> usize {
+	let before = ALLOCATIONS.with(Cell::get);
+	let output = f(input);
+	let after = ALLOCATIONS.with(Cell::get);
+	drop(output);
+
+	after - before
+}
+
+/// Asserts that `f` allocates as often for one block of input as for thousands.
+fn assert_allocations_do_not_grow(name: &str, input: impl Fn(usize) -> Vec<u8>, f: impl Fn(Vec<u8>) -> usize) {
+	// The first IV drawn on a thread sets up its random number generator, which allocates.
+	f(input(BLOCK_SIZE));
+
+	let small = f(input(BLOCK_SIZE + 3));
+	let large = f(input(4096 * BLOCK_SIZE + 3));
+
+	assert_eq!(small, large, "{name}: {small} allocations for one block, {large} for 4096");
+}
+
+#[test]
+fn encrypting_allocates_the_same_for_any_length() {
+	let message = |len| vec![0x42; len];
+
+	assert_allocations_do_not_grow("ecb_encrypt", message, |m| allocations(m, |m| ecb_encrypt(m, KEY)));
+	assert_allocations_do_not_grow("cbc_encrypt", message, |m| allocations(m, |m| cbc_encrypt(m, KEY)));
+	assert_allocations_do_not_grow("ctr_encrypt", message, |m| allocations(m, |m| ctr_encrypt(m, KEY)));
+}
+
+#[test]
+fn decrypting_allocates_the_same_for_any_length() {
+	let ecb = |len| ecb_encrypt(vec![0x42; len], KEY);
+	let cbc = |len| cbc_encrypt(vec![0x42; len], KEY);
+	let ctr = |len| ctr_encrypt(vec![0x42; len], KEY);
+
+	assert_allocations_do_not_grow("ecb_decrypt", ecb, |c| allocations(c, |c| ecb_decrypt(c, KEY)));
+	assert_allocations_do_not_grow("cbc_decrypt", cbc, |c| allocations(c, |c| cbc_decrypt(c, KEY)));
+	assert_allocations_do_not_grow("ctr_decrypt", ctr, |c| allocations(c, |c| ctr_decrypt(c, KEY)));
+}
+
+#[test]
+fn ctr_decrypts_without_allocating() {
+	for blocks in [1, 4096] {
+		let cipher_text = ctr_encrypt(vec![0x42; blocks * BLOCK_SIZE + 3], KEY);
+
+		assert_eq!(allocations(cipher_text.clone(), |mut c| ctr_decrypt_in_place(&mut c, KEY).map(|()| c)), 0, "{blocks} blocks");
+		assert_eq!(allocations(cipher_text, |c| ctr_decrypt(c, KEY)), 0, "{blocks} blocks");
+	}
+}
+
+#[test]
+fn gcm_verifies_without_allocating() {
+	for blocks in [1, 4096] {
+		let cipher_text = gcm_encrypt(vec![0x42; blocks * BLOCK_SIZE + 3], KEY, b"header");
+
+		assert_eq!(allocations(cipher_text, |c| gcm_verify(&c, KEY, b"header").unwrap()), 0, "{blocks} blocks");
+	}
+}
+
+#[test]
+fn bounded_decrypts_refuse_oversized_input_without_allocating() {
+	let ecb = ecb_encrypt(vec![0x42; 4096 * BLOCK_SIZE], KEY);
+	let cbc = cbc_encrypt(vec![0x42; 4096 * BLOCK_SIZE], KEY);
+	let ctr = ctr_encrypt(vec![0x42; 4096 * BLOCK_SIZE], KEY);
+	let too_large = |result: Result<Vec<u8>, DecryptError>| assert_eq!(result, Err(DecryptError::OutputTooLarge));
+
+	assert_eq!(allocations(ecb, |c| too_large(ecb_decrypt_bounded(c, KEY, 1024))), 0, "ecb");
+	assert_eq!(allocations(cbc, |c| too_large(cbc_decrypt_bounded(c, KEY, 1024))), 0, "cbc");
+	assert_eq!(allocations(ctr, |c| too_large(ctr_decrypt_bounded(c, KEY, 1024))), 0, "ctr");
+}
+
+#[test]
+fn pad_allocates_at_most_once() {
+	for blocks in [1, 4096] {
+		// An exact-capacity input, so pad has to grow it, but only once.
+		let message = vec![0x42; blocks * BLOCK_SIZE + 3];
+
+		assert_eq!(allocations(message, pad), 1, "{blocks} blocks");
+	}
+}
+
+#[test]
+fn group_allocates_the_same_for_any_length() {
+	let padded = |len| pad(vec![0x42; len]);
+
+	assert_allocations_do_not_grow("group", padded, |p| allocations(p, |p| group(p).unwrap()));
+}
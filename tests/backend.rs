@@ -0,0 +1,17 @@
+use aes_modes::{active_backend, Backend};
+
+#[test]
+fn active_backend_reports_one_this_target_can_run() {
+	let possible: &[Backend] = if cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+		&[Backend::AesNi, Backend::Soft]
+	} else if cfg!(target_arch = "aarch64") {
+		&[Backend::Armv8, Backend::Soft]
+	} else {
+		&[Backend::Soft]
+	};
+	let backend = active_backend();
+
+	assert!(possible.contains(&backend), "{backend:?}");
+	// The CPU doesn't change between calls, so neither does the answer.
+	assert_eq!(active_backend(), backend);
+}
@@ -0,0 +1,49 @@
+use aes_modes::{BlockMode, Cbc, Ctr, DecryptError, Ecb, BLOCK_SIZE};
+
+const MODES: [&dyn BlockMode; 3] = [&Ecb, &Cbc, &Ctr];
+
+#[test]
+fn every_mode_round_trips_through_the_trait() {
+	let key = *b"YELLOW SUBMARINE";
+
+	for (i, mode) in MODES.into_iter().enumerate() {
+		for len in [0, 1, BLOCK_SIZE, 3 * BLOCK_SIZE + 5] {
+			let message = vec![0x42; len];
+			let cipher_text = mode.encrypt(message.clone(), key);
+
+			assert_eq!(mode.decrypt(cipher_text, key).unwrap(), message, "mode {i}, {len} bytes");
+		}
+	}
+}
+
+#[cfg(feature = "test-util")]
+#[test]
+fn every_mode_passes_the_shared_round_trip_check() {
+	use aes_modes::assert_roundtrip;
+
+	let samples: [&[u8]; 2] = [b"hello world", &[0x42; 3 * BLOCK_SIZE + 5]];
+	for mode in MODES {
+		assert_roundtrip(mode, *b"YELLOW SUBMARINE", &samples);
+	}
+	assert_roundtrip(&Cbc, [9u8; 24], &samples);
+	assert_roundtrip(&Ctr, [9u8; 32], &samples);
+}
+
+#[test]
+fn every_mode_rejects_empty_ciphertext() {
+	for mode in MODES {
+		assert_eq!(mode.decrypt(Vec::new(), [0; 16]), Err(DecryptError::Empty));
+	}
+}
+
+#[test]
+fn the_trait_works_with_larger_keys() {
+	fn round_trip<M: BlockMode<[u8; 32]>>(mode: M) {
+		let cipher_text = mode.encrypt(b"hello world".to_vec(), [9; 32]);
+		assert_eq!(mode.decrypt(cipher_text, [9; 32]).unwrap(), b"hello world");
+	}
+
+	round_trip(Ecb);
+	round_trip(Cbc);
+	round_trip(Ctr);
+}
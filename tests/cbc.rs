@@ -0,0 +1,195 @@
+use aes::{
+	cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit},
+	Aes128,
+};
+mod common;
+
+use aes_modes::{
+	cbc_decrypt, cbc_decrypt_detached, cbc_encrypt, cbc_encrypt_with_generated_iv, cbc_encrypt_with_iv, pad,
+	repeated_block_count, split_key_iv, DecryptError, BLOCK_SIZE,
+};
+use common::hex;
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+const MESSAGES: [&[u8]; 5] = [
+	b"",
+	b"hello world",
+	b"exactly sixteen!",
+	b"a message that spans a few blocks of plaintext",
+	&[0x42; 5 * BLOCK_SIZE + 3],
+];
+
+/// Undoes CBC by hand with the raw block cipher, independently of `cbc_decrypt`.
+fn decrypt_chain(cipher_text: &[u8]) -> Vec<u8> {
+	let cipher = Aes128::new(&KEY.into());
+	let mut plain_text = Vec::new();
+
+	for pair in cipher_text.chunks_exact(BLOCK_SIZE).collect::<Vec<_>>().windows(2) {
+		let mut block = GenericArray::clone_from_slice(pair[1]);
+		cipher.decrypt_block(&mut block);
+		plain_text.extend(block.iter().zip(pair[0]).map(|(a, b)| a ^ b));
+	}
+
+	plain_text
+}
+
+#[test]
+fn cbc_encrypt_chains_every_block_onto_the_previous_one() {
+	for message in MESSAGES {
+		let cipher_text = cbc_encrypt(message.to_vec(), KEY);
+
+		// The IV comes first, then one ciphertext block per padded plaintext block.
+		let padded = pad(message.to_vec());
+		assert_eq!(cipher_text.len(), padded.len() + BLOCK_SIZE);
+		assert_eq!(decrypt_chain(&cipher_text), padded);
+	}
+}
+
+#[test]
+fn cbc_encrypt_hides_repeated_plaintext_blocks() {
+	let cipher_text = cbc_encrypt(vec![0x42; 4 * BLOCK_SIZE], KEY);
+
+	assert_eq!(repeated_block_count(&cipher_text), 0);
+}
+
+#[test]
+fn cbc_round_trips() {
+	for message in MESSAGES {
+		let cipher_text = cbc_encrypt(message.to_vec(), KEY);
+
+		assert_eq!(cbc_decrypt(cipher_text, KEY).unwrap(), message);
+	}
+}
+
+#[test]
+fn cbc_decrypt_with_the_wrong_key_does_not_return_the_plaintext() {
+	let message = b"a message that spans a few blocks of plaintext";
+	let cipher_text = cbc_encrypt(message.to_vec(), KEY);
+
+	assert_ne!(cbc_decrypt(cipher_text, *b"PURPLE SUBMARINE").ok().as_deref(), Some(&message[..]));
+}
+
+/// A two-block ciphertext built by hand, c1 = E(p1 ^ IV) and c2 = E(p2 ^ c1), with every
+/// intermediate value written out. Decrypting block 2 only works if it is XORed with c1,
+/// the previous ciphertext block, and not with the recovered p1.
+#[test]
+fn cbc_decrypt_chains_on_the_previous_ciphertext_block() {
+	let cipher = Aes128::new(&KEY.into());
+	let encrypt = |block: &[u8]| {
+		let mut block = GenericArray::clone_from_slice(block);
+		cipher.encrypt_block(&mut block);
+		block.to_vec()
+	};
+
+	let iv = hex("000102030405060708090a0b0c0d0e0f");
+	// "first block, 16b", then "second block" with 4 bytes of padding.
+	let p1 = hex("666972737420626c6f636b2c20313662");
+	let p2 = hex("7365636f6e6420626c6f636b04040404");
+
+	let p1_xor_iv = hex("666870707025646b676a61272c3c386d");
+	let c1 = hex("628374207d42460591e29013f28fea77");
+	let p2_xor_c1 = hex("11e6174f13266667fd8df378f68bee73");
+	let c2 = hex("31276e4a4e59ae0273818ea1d317b936");
+
+	let xor = |a: &[u8], b: &[u8]| -> Vec<u8> { a.iter().zip(b).map(|(x, y)| x ^ y).collect() };
+	assert_eq!(xor(&p1, &iv), p1_xor_iv);
+	assert_eq!(encrypt(&p1_xor_iv), c1);
+	assert_eq!(xor(&p2, &c1), p2_xor_c1);
+	assert_eq!(encrypt(&p2_xor_c1), c2);
+
+	let plain_text = cbc_decrypt([iv, c1, c2].concat(), KEY).unwrap();
+
+	assert_eq!(plain_text, b"first block, 16bsecond block");
+}
+
+/// Why a CBC IV must be unpredictable, not just unique (the attack behind BEAST).
+///
+/// Say a system uses the last ciphertext block of one message as the IV of the next, and an
+/// attacker can have messages of their choosing encrypted. They have seen c = E(p ^ iv) for
+/// a secret block p and want to check a guess g. Knowing the next IV in advance, they submit
+/// g ^ iv ^ next_iv, which encrypts to E(g ^ iv): equal to c exactly when the guess is right.
+/// With a fresh random IV, next_iv isn't known when the chosen block has to be picked.
+#[test]
+fn a_predictable_iv_lets_a_chosen_plaintext_confirm_a_guess() {
+	let xor = |a: &[u8], b: &[u8]| -> Vec<u8> { a.iter().zip(b).map(|(x, y)| x ^ y).collect() };
+	let secret = b"PIN: 1234, ok?!!".to_vec();
+	let iv = [7u8; BLOCK_SIZE];
+
+	let victim = cbc_encrypt_with_iv(secret.clone(), KEY, iv);
+	let c = &victim[BLOCK_SIZE..2 * BLOCK_SIZE];
+	// The chained scheme: the next IV is the last block of the previous ciphertext.
+	let next_iv: [u8; BLOCK_SIZE] = victim[victim.len() - BLOCK_SIZE..].try_into().unwrap();
+
+	let confirms = |guess: &[u8]| {
+		let chosen = xor(&xor(guess, &iv), &next_iv);
+		let attack = cbc_encrypt_with_iv(chosen, KEY, next_iv);
+		&attack[BLOCK_SIZE..2 * BLOCK_SIZE] == c
+	};
+	assert!(confirms(&secret));
+	assert!(!confirms(b"PIN: 0000, ok?!!"));
+
+	// cbc_encrypt picks its own random IV, so the same chosen block tells the attacker nothing.
+	let chosen = xor(&xor(&secret, &iv), &next_iv);
+	let attack = cbc_encrypt(chosen, KEY);
+	assert_ne!(&attack[BLOCK_SIZE..2 * BLOCK_SIZE], c);
+}
+
+/// NIST SP 800-38A, F.2.1 CBC-AES128.Encrypt.
+#[test]
+fn cbc_matches_the_nist_vector() {
+	let key: [u8; 16] = hex("2b7e151628aed2a6abf7158809cf4f3c").try_into().unwrap();
+	let iv: [u8; 16] = hex("000102030405060708090a0b0c0d0e0f").try_into().unwrap();
+	let plain_text = hex(concat!(
+		"6bc1bee22e409f96e93d7e117393172a",
+		"ae2d8a571e03ac9c9eb76fac45af8e51",
+		"30c81c46a35ce411e5fbc1191a0a52ef",
+		"f69f2445df4f9b17ad2b417be66c3710",
+	));
+	let expected = hex(concat!(
+		"7649abac8119b246cee98e9b12e9197d",
+		"5086cb9b507219ee95db113a917678b2",
+		"73bed6b8e3c1743b7116e69e22229516",
+		"3ff1caa1681fac09120eca307586e1a7",
+	));
+
+	let cipher_text = cbc_encrypt_with_iv(plain_text.clone(), key, iv);
+
+	// IV, then the four vector blocks, then our padding block.
+	assert_eq!(cipher_text[..BLOCK_SIZE], iv);
+	assert_eq!(cipher_text[BLOCK_SIZE..BLOCK_SIZE + expected.len()], expected);
+	assert_eq!(cipher_text.len(), BLOCK_SIZE + expected.len() + BLOCK_SIZE);
+	assert_eq!(cbc_decrypt(cipher_text, key).unwrap(), plain_text);
+}
+
+#[test]
+fn stored_key_material_splits_into_key_then_iv() {
+	let combined: Vec<u8> = (0..2 * BLOCK_SIZE as u8).collect();
+	let (key, iv) = split_key_iv(&combined).unwrap();
+
+	assert_eq!(key[..], combined[..BLOCK_SIZE]);
+	assert_eq!(iv[..], combined[BLOCK_SIZE..]);
+	assert_eq!(cbc_encrypt_with_iv(b"hello world".to_vec(), key, iv)[..BLOCK_SIZE], iv);
+
+	for len in [0, 2 * BLOCK_SIZE - 1, 2 * BLOCK_SIZE + 1] {
+		assert_eq!(split_key_iv(&vec![0; len]), Err(DecryptError::InvalidLength), "{len}");
+	}
+}
+
+#[test]
+fn a_generated_iv_comes_back_separately_from_the_cipher_text() {
+	for message in MESSAGES {
+		let (iv, cipher_text) = cbc_encrypt_with_generated_iv(message.to_vec(), KEY);
+
+		// Exactly what cbc_encrypt_with_iv would give, minus the IV block at the front.
+		assert_eq!(cipher_text, cbc_encrypt_with_iv(message.to_vec(), KEY, iv)[BLOCK_SIZE..]);
+		assert_eq!(cbc_decrypt_detached(cipher_text, KEY, iv).unwrap(), message);
+	}
+
+	let (first, _) = cbc_encrypt_with_generated_iv(b"hello world".to_vec(), KEY);
+	let (second, _) = cbc_encrypt_with_generated_iv(b"hello world".to_vec(), KEY);
+	assert_ne!(first, second);
+
+	assert_eq!(cbc_decrypt_detached(Vec::new(), KEY, first), Err(DecryptError::Empty));
+	assert_eq!(cbc_decrypt_detached(vec![0; 17], KEY, first), Err(DecryptError::InvalidLength));
+}
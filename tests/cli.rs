@@ -0,0 +1,161 @@
+use std::{
+	fs,
+	path::PathBuf,
+	process::{Command, Output},
+};
+
+const KEY: &str = "000102030405060708090a0b0c0d0e0f";
+
+fn cli(args: &[&str]) -> Output {
+	cli_with_env(args, &[])
+}
+
+fn cli_with_env(args: &[&str], vars: &[(&str, &str)]) -> Output {
+	Command::new(env!("CARGO_BIN_EXE_aes-mode-activity")).args(args).envs(vars.iter().copied()).output().unwrap()
+}
+
+/// A path in the temp directory that no other test uses.
+fn temp_path(name: &str) -> PathBuf {
+	std::env::temp_dir().join(format!("aes-modes-cli-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn files_round_trip_through_every_mode() {
+	let plain = temp_path("plain.bin");
+	let message = b"a message that spans a few blocks of plaintext";
+	fs::write(&plain, message).unwrap();
+
+	for mode in ["ecb", "cbc", "ctr"] {
+		let cipher = temp_path(&format!("{mode}.enc"));
+		let decrypted = temp_path(&format!("{mode}.dec"));
+
+		let encrypt = cli(&["encrypt", "--mode", mode, "--key", KEY, "--in", plain.to_str().unwrap(), "--out", cipher.to_str().unwrap()]);
+		assert!(encrypt.status.success(), "{mode}: {}", String::from_utf8_lossy(&encrypt.stderr));
+		assert_ne!(fs::read(&cipher).unwrap(), message);
+
+		let decrypt = cli(&["decrypt", "--mode", mode, "--key", KEY, "--in", cipher.to_str().unwrap(), "--out", decrypted.to_str().unwrap()]);
+		assert!(decrypt.status.success(), "{mode}: {}", String::from_utf8_lossy(&decrypt.stderr));
+		assert_eq!(fs::read(&decrypted).unwrap(), message);
+
+		fs::remove_file(cipher).unwrap();
+		fs::remove_file(decrypted).unwrap();
+	}
+
+	fs::remove_file(plain).unwrap();
+}
+
+#[test]
+fn the_key_can_come_from_an_environment_variable() {
+	let plain = temp_path("env-plain.bin");
+	let cipher = temp_path("env.enc");
+	let decrypted = temp_path("env.dec");
+	let message = b"a message encrypted with a key from the environment";
+	fs::write(&plain, message).unwrap();
+
+	let (plain, cipher, decrypted) = (plain.to_str().unwrap(), cipher.to_str().unwrap(), decrypted.to_str().unwrap());
+	let vars = [("AES_MODES_TEST_KEY", KEY)];
+
+	let encrypt = cli_with_env(&["encrypt", "--mode", "cbc", "--key-env", "AES_MODES_TEST_KEY", "--in", plain, "--out", cipher], &vars);
+	assert!(encrypt.status.success(), "{}", String::from_utf8_lossy(&encrypt.stderr));
+
+	// The same key given directly decrypts it.
+	let decrypt = cli(&["decrypt", "--mode", "cbc", "--key", KEY, "--in", cipher, "--out", decrypted]);
+	assert!(decrypt.status.success(), "{}", String::from_utf8_lossy(&decrypt.stderr));
+	assert_eq!(fs::read(decrypted).unwrap(), message);
+
+	let unset = cli(&["encrypt", "--mode", "cbc", "--key-env", "AES_MODES_UNSET_KEY", "--in", plain, "--out", cipher]);
+	assert!(String::from_utf8_lossy(&unset.stderr).contains("`AES_MODES_UNSET_KEY` is not set"));
+
+	let malformed = cli_with_env(&["encrypt", "--mode", "cbc", "--key-env", "AES_MODES_TEST_KEY", "--in", plain, "--out", cipher], &[("AES_MODES_TEST_KEY", "0011")]);
+	assert_eq!(malformed.status.code(), Some(1));
+	assert!(String::from_utf8_lossy(&malformed.stderr).contains("`AES_MODES_TEST_KEY`: key must be"));
+
+	for path in [plain, cipher, decrypted] {
+		fs::remove_file(path).unwrap();
+	}
+}
+
+#[test]
+fn cbc_can_keep_the_iv_in_a_sidecar_file() {
+	let plain = temp_path("sidecar-plain.bin");
+	let cipher = temp_path("sidecar.enc");
+	let iv = temp_path("sidecar.iv");
+	let decrypted = temp_path("sidecar.dec");
+	let message = b"a message whose IV is stored next to it";
+	fs::write(&plain, message).unwrap();
+
+	let (plain, cipher, iv, decrypted) =
+		(plain.to_str().unwrap(), cipher.to_str().unwrap(), iv.to_str().unwrap(), decrypted.to_str().unwrap());
+
+	let encrypt = cli(&["encrypt", "--mode", "cbc", "--key", KEY, "--in", plain, "--out", cipher, "--iv-file", iv]);
+	assert!(encrypt.status.success(), "{}", String::from_utf8_lossy(&encrypt.stderr));
+	assert_eq!(fs::read(iv).unwrap().len(), 16);
+	// Just the padded plaintext, with no IV block in front.
+	assert_eq!(fs::read(cipher).unwrap().len(), message.len().next_multiple_of(16));
+
+	let decrypt = cli(&["decrypt", "--mode", "cbc", "--key", KEY, "--in", cipher, "--out", decrypted, "--iv-file", iv]);
+	assert!(decrypt.status.success(), "{}", String::from_utf8_lossy(&decrypt.stderr));
+	assert_eq!(fs::read(decrypted).unwrap(), message);
+
+	fs::write(iv, [0; 15]).unwrap();
+	let short_iv = cli(&["decrypt", "--mode", "cbc", "--key", KEY, "--in", cipher, "--out", decrypted, "--iv-file", iv]);
+	assert_eq!(short_iv.status.code(), Some(1));
+	assert!(String::from_utf8_lossy(&short_iv.stderr).contains("must hold exactly 16 bytes of IV, got 15"));
+
+	let not_cbc = cli(&["encrypt", "--mode", "ctr", "--key", KEY, "--in", plain, "--out", cipher, "--iv-file", iv]);
+	assert!(String::from_utf8_lossy(&not_cbc.stderr).contains("--iv-file only works with --mode cbc"));
+
+	for path in [plain, cipher, iv, decrypted] {
+		fs::remove_file(path).unwrap();
+	}
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn the_key_can_be_given_as_base64() {
+	let plain = temp_path("base64-plain.bin");
+	let cipher = temp_path("base64.enc");
+	let decrypted = temp_path("base64.dec");
+	let message = b"a message encrypted with a base64 key";
+	fs::write(&plain, message).unwrap();
+
+	let (plain, cipher, decrypted) = (plain.to_str().unwrap(), cipher.to_str().unwrap(), decrypted.to_str().unwrap());
+
+	// The same 16 bytes as KEY.
+	let encrypt = cli(&["encrypt", "--mode", "ctr", "--key-base64", "AAECAwQFBgcICQoLDA0ODw==", "--in", plain, "--out", cipher]);
+	assert!(encrypt.status.success(), "{}", String::from_utf8_lossy(&encrypt.stderr));
+
+	let decrypt = cli(&["decrypt", "--mode", "ctr", "--key", KEY, "--in", cipher, "--out", decrypted]);
+	assert!(decrypt.status.success(), "{}", String::from_utf8_lossy(&decrypt.stderr));
+	assert_eq!(fs::read(decrypted).unwrap(), message);
+
+	let short = cli(&["encrypt", "--mode", "ctr", "--key-base64", "AAECAwQF", "--in", plain, "--out", cipher]);
+	assert_eq!(short.status.code(), Some(1));
+	assert!(String::from_utf8_lossy(&short.stderr).contains("got 6"));
+
+	for path in [plain, cipher, decrypted] {
+		fs::remove_file(path).unwrap();
+	}
+}
+
+#[test]
+fn bad_input_exits_with_an_error_instead_of_panicking() {
+	let missing = temp_path("does-not-exist");
+	let out = temp_path("unused.out");
+	let cases: [&[&str]; 6] = [
+		&[],
+		&["encrypt", "--mode", "ecb", "--key", KEY, "--key-env", "AES_MODES_TEST_KEY", "--in", "a", "--out", "b"],
+		&["encrypt", "--mode", "xts", "--key", KEY, "--in", "a", "--out", "b"],
+		&["encrypt", "--mode", "ecb", "--key", "not hex at all, but 32 chars ok!", "--in", "a", "--out", "b"],
+		&["encrypt", "--mode", "ecb", "--key", "0011", "--in", "a", "--out", "b"],
+		&["encrypt", "--mode", "ecb", "--key", KEY, "--in", missing.to_str().unwrap(), "--out", out.to_str().unwrap()],
+	];
+
+	for args in cases {
+		let output = cli(args);
+
+		assert_eq!(output.status.code(), Some(1), "{args:?}");
+		assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "), "{args:?}");
+	}
+	assert!(!out.exists());
+}
@@ -0,0 +1,194 @@
+use aes_modes::{
+	bytes_to_counter, counter_to_bytes, ctr_decrypt, ctr_decrypt_with_layout, ctr_encrypt, ctr_encrypt_with_layout,
+	ctr_decrypt_in_place, ctr_decrypt_prefix, ctr_encrypt_seq, ctr_encrypt_timestamped, ctr_encrypt_with_nonce, ecb_encrypt, CounterLayout, DecryptError, BLOCK_SIZE,
+};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+/// Messages of 11 and 16 bytes and 4 blocks plus 7 bytes. CTR encrypts them at their exact
+/// length, with no padding.
+fn messages() -> Vec<Vec<u8>> {
+	vec![b"hello world".to_vec(), b"exactly sixteen!".to_vec(), vec![0x42; 4 * BLOCK_SIZE + 7]]
+}
+
+#[test]
+fn ctr_round_trips() {
+	for message in messages() {
+		let cipher_text = ctr_encrypt(message.clone(), KEY);
+
+		assert_eq!(ctr_decrypt(cipher_text, KEY).unwrap(), message);
+	}
+}
+
+#[test]
+fn ctr_round_trips_with_either_counter_layout() {
+	for layout in [CounterLayout::NonceThenCounter, CounterLayout::CounterThenNonce] {
+		for message in messages() {
+			let cipher_text = ctr_encrypt_with_layout(message.clone(), KEY, layout);
+
+			assert_eq!(ctr_decrypt_with_layout(cipher_text, KEY, layout).unwrap(), message);
+		}
+	}
+}
+
+#[test]
+fn each_counter_layout_builds_v_in_its_own_order() {
+	for layout in [CounterLayout::NonceThenCounter, CounterLayout::CounterThenNonce] {
+		// Encrypting zeros exposes the keystream, which is E(K, V) for each counter value.
+		let cipher_text = ctr_encrypt_with_layout(vec![0; 3 * BLOCK_SIZE], KEY, layout);
+		let (nonce, keystream) = cipher_text.split_at(BLOCK_SIZE / 2);
+
+		for (counter, block) in keystream.chunks_exact(BLOCK_SIZE).enumerate() {
+			let counter = (counter as u64).to_be_bytes();
+			let v = match layout {
+				CounterLayout::NonceThenCounter => [nonce, &counter].concat(),
+				CounterLayout::CounterThenNonce => [&counter, nonce].concat(),
+			};
+
+			assert_eq!(block, &ecb_encrypt(v, KEY)[..BLOCK_SIZE], "{layout:?}, counter {counter:?}");
+		}
+	}
+}
+
+#[test]
+fn sequence_numbers_give_distinct_nonces_and_replay_identically() {
+	let message = b"hello world".to_vec();
+	let nonces: Vec<Vec<u8>> =
+		(0..100).map(|seq| ctr_encrypt_seq(message.clone(), KEY, seq)[..BLOCK_SIZE / 2].to_vec()).collect();
+
+	for (seq, nonce) in nonces.iter().enumerate() {
+		assert_eq!(*nonce, (seq as u64).to_be_bytes());
+	}
+
+	let cipher_text = ctr_encrypt_seq(message.clone(), KEY, 42);
+	assert_eq!(cipher_text, ctr_encrypt_seq(message.clone(), KEY, 42));
+	assert_ne!(cipher_text, ctr_encrypt_seq(message.clone(), KEY, 43));
+	assert_eq!(ctr_decrypt(cipher_text, KEY).unwrap(), message);
+}
+
+#[test]
+fn timestamped_nonces_start_with_the_time() {
+	let message = b"hello world".to_vec();
+	let unix_time: u32 = 1_700_000_000;
+
+	let first = ctr_encrypt_timestamped(message.clone(), KEY, unix_time);
+	let second = ctr_encrypt_timestamped(message.clone(), KEY, unix_time);
+	assert_eq!(first[..4], unix_time.to_be_bytes());
+	assert_eq!(second[..4], unix_time.to_be_bytes());
+
+	// The same second, but the random half still tells them apart.
+	assert_ne!(first[4..BLOCK_SIZE / 2], second[4..BLOCK_SIZE / 2]);
+	assert_eq!(ctr_decrypt(first, KEY).unwrap(), message);
+}
+
+#[test]
+fn ctr_with_a_fixed_nonce_is_reproducible() {
+	let nonce = *b"8 bytes!";
+	let message = vec![0x42; 4 * BLOCK_SIZE + 7];
+
+	let cipher_text = ctr_encrypt_with_nonce(message.clone(), KEY, nonce);
+
+	assert_eq!(cipher_text, ctr_encrypt_with_nonce(message.clone(), KEY, nonce));
+	assert_eq!(cipher_text[..BLOCK_SIZE / 2], nonce);
+	assert_eq!(ctr_decrypt(cipher_text, KEY).unwrap(), message);
+}
+
+#[test]
+fn ctr_nonce_is_genuinely_eight_bytes() {
+	let message = vec![0x42; 3 * BLOCK_SIZE];
+
+	// An 8-byte nonce and the three message blocks. CTR adds no padding.
+	assert_eq!(ctr_encrypt(message, KEY).len(), BLOCK_SIZE / 2 + 3 * BLOCK_SIZE);
+}
+
+#[test]
+fn ctr_ciphertext_is_the_message_length_plus_the_nonce() {
+	for len in [0, 1, 15, 16, 17, 33] {
+		let message = vec![0x42; len];
+		let cipher_text = ctr_encrypt(message.clone(), KEY);
+
+		assert_eq!(cipher_text.len(), len + BLOCK_SIZE / 2, "{len}");
+		assert_eq!(ctr_decrypt(cipher_text, KEY).unwrap(), message, "{len}");
+	}
+}
+
+#[test]
+fn ctr_counter_carries_from_block_to_block() {
+	let nonce = *b"8 bytes!";
+
+	// Encrypting zeros exposes the keystream. 300 blocks take the counter past 0xff, so the
+	// low byte has to carry into the next one.
+	let cipher_text = ctr_encrypt_with_nonce(vec![0; 300 * BLOCK_SIZE], KEY, nonce);
+	let keystream = &cipher_text[BLOCK_SIZE / 2..];
+
+	for (counter, block) in keystream.chunks_exact(BLOCK_SIZE).take(300).enumerate() {
+		let mut v = nonce.to_vec();
+		v.extend((counter as u64).to_be_bytes());
+
+		assert_eq!(block, &ecb_encrypt(v, KEY)[..BLOCK_SIZE], "counter {counter}");
+	}
+}
+
+#[test]
+fn ctr_keeps_trailing_bytes_that_look_like_padding() {
+	// Each of these ends in valid PKCS#7 padding. CTR never pads, so none of it may come off.
+	for tail in [&[1][..], &[3, 3, 3], &[BLOCK_SIZE as u8; BLOCK_SIZE]] {
+		let mut message = b"hello world".to_vec();
+		message.extend_from_slice(tail);
+
+		let cipher_text = ctr_encrypt(message.clone(), KEY);
+
+		assert_eq!(cipher_text.len(), BLOCK_SIZE / 2 + message.len());
+		assert_eq!(ctr_decrypt(cipher_text, KEY).unwrap(), message, "{tail:?}");
+	}
+}
+
+#[test]
+fn counter_bytes_round_trip() {
+	for counter in [0, 1, 0xff, 0x100, u32::MAX as u64, u32::MAX as u64 + 1, u64::MAX - 1, u64::MAX] {
+		assert_eq!(bytes_to_counter(&counter_to_bytes(counter)), counter, "{counter:#x}");
+	}
+	for bytes in [[0; 8], [0xff; 8], *b"8 bytes!"] {
+		assert_eq!(counter_to_bytes(bytes_to_counter(&bytes)), bytes, "{bytes:?}");
+	}
+}
+
+#[test]
+fn counter_bytes_are_big_endian() {
+	assert_eq!(counter_to_bytes(0xff), [0, 0, 0, 0, 0, 0, 0, 0xff]);
+	assert_eq!(counter_to_bytes(0x100), [0, 0, 0, 0, 0, 0, 1, 0]);
+	assert_eq!(counter_to_bytes(u32::MAX as u64), [0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+	assert_eq!(counter_to_bytes(u32::MAX as u64 + 1), [0, 0, 0, 1, 0, 0, 0, 0]);
+}
+
+#[test]
+fn a_prefix_decrypts_to_the_start_of_the_full_plaintext() {
+	let mut file = b"\x7fELF".to_vec();
+	file.extend(vec![0x42; 4 * BLOCK_SIZE + 7]);
+	let cipher_text = ctr_encrypt(file.clone(), KEY);
+
+	let magic = ctr_decrypt_prefix(&cipher_text, KEY, 4).unwrap();
+	assert_eq!(magic, b"\x7fELF");
+	assert_eq!(magic, ctr_decrypt(cipher_text.clone(), KEY).unwrap()[..4]);
+
+	// Past the first block, and past the end.
+	assert_eq!(ctr_decrypt_prefix(&cipher_text, KEY, BLOCK_SIZE + 3).unwrap(), file[..BLOCK_SIZE + 3]);
+	assert_eq!(ctr_decrypt_prefix(&cipher_text, KEY, 1000).unwrap(), file);
+	assert_eq!(ctr_decrypt_prefix(&cipher_text[..5], KEY, 4), Err(DecryptError::InvalidLength));
+}
+
+#[test]
+fn decrypting_in_place_matches_ctr_decrypt() {
+	for message in messages().into_iter().chain([Vec::new()]) {
+		let cipher_text = ctr_encrypt(message.clone(), KEY);
+
+		let mut buf = cipher_text.clone();
+		ctr_decrypt_in_place(&mut buf, KEY).unwrap();
+		assert_eq!(buf, ctr_decrypt(cipher_text, KEY).unwrap());
+		assert_eq!(buf, message);
+	}
+
+	let mut short = vec![1, 2, 3];
+	assert_eq!(ctr_decrypt_in_place(&mut short, KEY), Err(DecryptError::InvalidLength));
+	assert_eq!(short, [1, 2, 3]);
+}
@@ -0,0 +1,22 @@
+use aes_modes::debug_blocks;
+
+#[test]
+fn a_short_message_is_one_block_with_its_padding_marked() {
+	let expected = concat!(
+		"block 0: 68 65 6c 6c 6f 0b 0b 0b 0b 0b 0b 0b 0b 0b 0b 0b\n",
+		"                        ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ padding\n",
+	);
+
+	assert_eq!(debug_blocks(b"hello"), expected);
+}
+
+#[test]
+fn a_whole_block_message_is_followed_by_a_full_padding_block() {
+	let expected = concat!(
+		"block 0: 65 78 61 63 74 6c 79 20 73 69 78 74 65 65 6e 21\n",
+		"block 1: 10 10 10 10 10 10 10 10 10 10 10 10 10 10 10 10\n",
+		"         ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ ^^ padding\n",
+	);
+
+	assert_eq!(debug_blocks(b"exactly sixteen!"), expected);
+}
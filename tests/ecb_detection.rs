@@ -0,0 +1,74 @@
+use aes_modes::{cbc_encrypt, ct_ciphertext_eq, ecb_encrypt, has_repeated_blocks, repeated_block_count, BLOCK_SIZE};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+/// Twelve copies of the same block, like the flat background of the ECB penguin.
+fn repetitive() -> Vec<u8> {
+	b"YELLOW SUBMARINE".repeat(12)
+}
+
+#[test]
+fn ecb_of_repetitive_plaintext_is_flagged() {
+	let cipher_text = ecb_encrypt(repetitive(), KEY);
+
+	assert!(has_repeated_blocks(&cipher_text));
+	assert_eq!(repeated_block_count(&cipher_text), 11);
+}
+
+#[test]
+fn cbc_of_the_same_plaintext_is_not() {
+	let cipher_text = cbc_encrypt(repetitive(), KEY);
+
+	assert!(!has_repeated_blocks(&cipher_text));
+	assert_eq!(repeated_block_count(&cipher_text), 0);
+}
+
+#[test]
+fn random_input_is_not_flagged() {
+	let data: Vec<u8> = (0..64 * BLOCK_SIZE).map(|_| rand::random()).collect();
+
+	assert!(!has_repeated_blocks(&data));
+	assert!(!has_repeated_blocks(&ecb_encrypt(data, KEY)));
+}
+
+#[test]
+fn a_trailing_partial_block_is_ignored() {
+	let mut data = vec![7; BLOCK_SIZE];
+	data.extend([7; BLOCK_SIZE - 1]);
+
+	assert!(!has_repeated_blocks(&data));
+	assert!(!has_repeated_blocks(&[]));
+}
+
+#[test]
+fn repeated_block_count_counts_every_repeat_after_the_first() {
+	let a = [b'A'; BLOCK_SIZE];
+	let b = [b'B'; BLOCK_SIZE];
+	let c = [b'C'; BLOCK_SIZE];
+
+	assert_eq!(repeated_block_count(&[a, b, a, a].concat()), 2);
+	assert_eq!(repeated_block_count(&[a, b, c, a, b, a].concat()), 3);
+	assert_eq!(repeated_block_count(&[a, b, c].concat()), 0);
+	assert_eq!(repeated_block_count(&[]), 0);
+}
+
+#[test]
+fn ct_ciphertext_eq_compares_the_whole_ciphertext() {
+	let cipher_text = ecb_encrypt(repetitive(), KEY);
+	let mut last_byte_differs = cipher_text.clone();
+	*last_byte_differs.last_mut().unwrap() ^= 1;
+
+	assert!(ct_ciphertext_eq(&cipher_text, &ecb_encrypt(repetitive(), KEY)));
+	assert!(!ct_ciphertext_eq(&cipher_text, &last_byte_differs));
+	assert!(ct_ciphertext_eq(&[], &[]));
+}
+
+#[test]
+fn ct_ciphertext_eq_is_false_for_different_lengths() {
+	let cipher_text = ecb_encrypt(repetitive(), KEY);
+
+	// A prefix matches byte for byte as far as it goes, but is still not equal.
+	assert!(!ct_ciphertext_eq(&cipher_text, &cipher_text[..BLOCK_SIZE]));
+	assert!(!ct_ciphertext_eq(&cipher_text[..BLOCK_SIZE], &cipher_text));
+	assert!(!ct_ciphertext_eq(&cipher_text, &[]));
+}
@@ -0,0 +1,52 @@
+//! The zero-length boundary: empty plaintexts round-trip in every mode, and decrypting
+//! nothing is an error rather than a panic.
+
+use aes_modes::{
+	cbc_decrypt, cbc_encrypt, ctr_decrypt, ctr_encrypt, ecb_decrypt, ecb_encrypt, gcm_decrypt, gcm_encrypt, pad, un_pad,
+	DecryptError, BLOCK_SIZE, GCM_NONCE_SIZE, GCM_TAG_SIZE,
+};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+#[test]
+fn empty_data_pads_to_one_full_block() {
+	assert_eq!(pad(Vec::new()), vec![BLOCK_SIZE as u8; BLOCK_SIZE]);
+	assert_eq!(un_pad(vec![BLOCK_SIZE as u8; BLOCK_SIZE]).unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn un_pad_of_nothing_is_an_error() {
+	assert_eq!(un_pad(Vec::new()), Err(DecryptError::Empty));
+}
+
+#[test]
+fn empty_ecb_is_one_padding_block() {
+	let cipher_text = ecb_encrypt(Vec::new(), KEY);
+
+	assert_eq!(cipher_text.len(), BLOCK_SIZE);
+	assert_eq!(ecb_decrypt(cipher_text, KEY).unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn empty_cbc_is_the_iv_and_one_padding_block() {
+	let cipher_text = cbc_encrypt(Vec::new(), KEY);
+
+	assert_eq!(cipher_text.len(), 2 * BLOCK_SIZE);
+	assert_eq!(cbc_decrypt(cipher_text, KEY).unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn empty_ctr_is_just_the_nonce() {
+	let cipher_text = ctr_encrypt(Vec::new(), KEY);
+
+	assert_eq!(cipher_text.len(), BLOCK_SIZE / 2);
+	assert_eq!(ctr_decrypt(cipher_text, KEY).unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn empty_gcm_is_the_nonce_and_the_tag() {
+	let cipher_text = gcm_encrypt(Vec::new(), KEY, b"still authenticated");
+
+	assert_eq!(cipher_text.len(), GCM_NONCE_SIZE + GCM_TAG_SIZE);
+	assert_eq!(gcm_decrypt(cipher_text, KEY, b"still authenticated").unwrap(), Vec::<u8>::new());
+}
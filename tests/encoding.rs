@@ -0,0 +1,105 @@
+use aes_modes::{
+	cbc_decrypt_hex, cbc_encrypt_hex, ctr_decrypt_hex, ctr_encrypt_hex, ecb_decrypt, ecb_decrypt_hex, ecb_encrypt,
+	ecb_encrypt_hex, hex_decode, hex_encode, DecryptError, EncodingError,
+};
+
+const KEY: &str = "000102030405060708090a0b0c0d0e0f";
+const KEY_192: &str = "000102030405060708090a0b0c0d0e0f1011121314151617";
+const KEY_256: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+#[test]
+fn hex_round_trips() {
+	let bytes: Vec<u8> = (0..=255).collect();
+
+	assert_eq!(hex_decode(&hex_encode(&bytes)).unwrap(), bytes);
+	assert_eq!(hex_decode("ABcd").unwrap(), [0xab, 0xcd]);
+	assert_eq!(hex_encode(&[0x0f, 0xa0]), "0fa0");
+}
+
+#[test]
+fn hex_decode_rejects_odd_lengths_and_non_hex() {
+	for bad in ["abc", "zz", "+f", "0x12", "é0", "12 34"] {
+		assert_eq!(hex_decode(bad), Err(EncodingError::InvalidHex), "{bad}");
+	}
+}
+
+#[test]
+fn hex_wrappers_round_trip_every_mode() {
+	let message = "a message that spans a few blocks of plaintext";
+
+	assert_eq!(ecb_decrypt_hex(&ecb_encrypt_hex(message, KEY).unwrap(), KEY).unwrap(), message);
+	assert_eq!(cbc_decrypt_hex(&cbc_encrypt_hex(message, KEY).unwrap(), KEY).unwrap(), message);
+	assert_eq!(ctr_decrypt_hex(&ctr_encrypt_hex(message, KEY).unwrap(), KEY).unwrap(), message);
+}
+
+#[test]
+fn hex_ciphertext_is_the_byte_ciphertext_encoded() {
+	let cipher_text = ecb_encrypt_hex("hello world", KEY).unwrap();
+	let key: [u8; 16] = hex_decode(KEY).unwrap().try_into().unwrap();
+
+	assert_eq!(ecb_decrypt(hex_decode(&cipher_text).unwrap(), key).unwrap(), b"hello world");
+}
+
+#[test]
+fn hex_wrappers_take_aes192_and_aes256_keys() {
+	let message = "a message that spans a few blocks of plaintext";
+
+	for key in [KEY_192, KEY_256] {
+		assert_eq!(ecb_decrypt_hex(&ecb_encrypt_hex(message, key).unwrap(), key).unwrap(), message);
+		assert_eq!(cbc_decrypt_hex(&cbc_encrypt_hex(message, key).unwrap(), key).unwrap(), message);
+		assert_eq!(ctr_decrypt_hex(&ctr_encrypt_hex(message, key).unwrap(), key).unwrap(), message);
+	}
+
+	// An AES-256 key really encrypts with AES-256.
+	let key: [u8; 32] = hex_decode(KEY_256).unwrap().try_into().unwrap();
+	let cipher_text = ecb_encrypt_hex(message, KEY_256).unwrap();
+	assert_eq!(hex_decode(&cipher_text).unwrap(), ecb_encrypt(message.as_bytes().to_vec(), key));
+}
+
+#[test]
+fn bad_keys_and_ciphertext_are_errors() {
+	assert_eq!(ecb_encrypt_hex("hi", "0011"), Err(EncodingError::InvalidKeyLength));
+	assert_eq!(ecb_encrypt_hex("hi", &KEY_256[..62]), Err(EncodingError::InvalidKeyLength));
+	assert_eq!(ecb_encrypt_hex("hi", &KEY[1..]), Err(EncodingError::InvalidHex));
+	assert_eq!(ecb_decrypt_hex("not hex", KEY), Err(EncodingError::InvalidHex));
+	assert_eq!(ecb_decrypt_hex("", KEY), Err(EncodingError::Decrypt(DecryptError::Empty)));
+}
+
+#[test]
+fn non_utf8_plaintext_is_an_error() {
+	// Decrypt a ciphertext whose plaintext is a lone 0xff byte.
+	let key: [u8; 16] = hex_decode(KEY).unwrap().try_into().unwrap();
+	let cipher_text = hex_encode(&ecb_encrypt(vec![0xff], key));
+
+	assert_eq!(ecb_decrypt_hex(&cipher_text, KEY), Err(EncodingError::InvalidUtf8));
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn base64_wrappers_round_trip_every_mode() {
+	use aes_modes::{cbc_decrypt_base64, cbc_encrypt_base64, ctr_decrypt_base64, ctr_encrypt_base64, ecb_decrypt_base64, ecb_encrypt_base64};
+
+	let message = "a message that spans a few blocks of plaintext";
+
+	assert_eq!(ecb_decrypt_base64(&ecb_encrypt_base64(message, KEY).unwrap(), KEY).unwrap(), message);
+	assert_eq!(cbc_decrypt_base64(&cbc_encrypt_base64(message, KEY).unwrap(), KEY).unwrap(), message);
+	assert_eq!(ctr_decrypt_base64(&ctr_encrypt_base64(message, KEY).unwrap(), KEY).unwrap(), message);
+	assert_eq!(ecb_decrypt_base64("not base64!", KEY), Err(EncodingError::InvalidBase64));
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn keys_decode_from_base64_at_the_requested_size() {
+	use aes_modes::{base64_encode, key_from_base64};
+
+	let key_128: [u8; 16] = key_from_base64("AAECAwQFBgcICQoLDA0ODw==").unwrap();
+	assert_eq!(key_128, *hex_decode(KEY).unwrap());
+
+	let key_256: [u8; 32] = key_from_base64(&base64_encode(&hex_decode(KEY_256).unwrap())).unwrap();
+	assert_eq!(key_256, *hex_decode(KEY_256).unwrap());
+
+	// Too short, and the wrong size for the key type asked for.
+	assert_eq!(key_from_base64::<[u8; 16]>("AAECAwQFBgcICQoLDA0O"), Err(EncodingError::InvalidKeyLength));
+	assert_eq!(key_from_base64::<[u8; 32]>("AAECAwQFBgcICQoLDA0ODw=="), Err(EncodingError::InvalidKeyLength));
+	assert_eq!(key_from_base64::<[u8; 16]>("not base64!"), Err(EncodingError::InvalidBase64));
+}
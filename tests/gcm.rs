@@ -0,0 +1,327 @@
+mod common;
+
+use aes_modes::{
+	gcm_decrypt, gcm_decrypt_detached, gcm_decrypt_with_aad_list, gcm_decrypt_with_iv, gcm_decrypt_with_tag_len,
+	gcm_encrypt, gcm_encrypt_detached, gcm_encrypt_with_aad_list, gcm_encrypt_with_iv, gcm_encrypt_with_nonce,
+	gcm_encrypt_with_tag_len, gcm_verify, DecryptError, GCM_MIN_TAG_SIZE, GCM_NONCE_SIZE, GCM_TAG_SIZE,
+};
+use common::hex;
+
+const PLAIN_TEXT: &str = concat!(
+	"d9313225f88406e5a55909c5aff5269a",
+	"86a7a9531534f7da2e4c303d8a318a72",
+	"1c3c0c95956809532fcf0e2449a6b525",
+	"b16aedf5aa0de657ba637b391aafd255",
+);
+const ASSOCIATED_DATA: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+const NONCE: &str = "cafebabefacedbaddecaf888";
+
+/// Runs one vector from the original GCM specification (McGrew and Viega, appendix B).
+fn check_vector<K: aes_modes::AesKey + Copy>(key: K, nonce: &str, plain_text: &[u8], associated_data: &[u8], expected: &str, tag: &str) {
+	let nonce: [u8; GCM_NONCE_SIZE] = hex(nonce).try_into().unwrap();
+	let cipher_text = gcm_encrypt_with_nonce(plain_text.to_vec(), key, associated_data, nonce);
+
+	assert_eq!(cipher_text[..GCM_NONCE_SIZE], nonce);
+	assert_eq!(hex_string(&cipher_text[GCM_NONCE_SIZE..cipher_text.len() - GCM_TAG_SIZE]), expected);
+	assert_eq!(hex_string(&cipher_text[cipher_text.len() - GCM_TAG_SIZE..]), tag);
+	assert_eq!(gcm_decrypt(cipher_text, key, associated_data).unwrap(), plain_text);
+}
+
+/// Same as check_vector, for the vectors whose IV isn't 96 bits, so J0 comes from GHASH.
+fn check_iv_vector<K: aes_modes::AesKey + Copy>(key: K, iv: &str, expected: &str, tag: &str) {
+	let plain_text = &hex(PLAIN_TEXT)[..60];
+	let associated_data = hex(ASSOCIATED_DATA);
+	let cipher_text = gcm_encrypt_with_iv(plain_text.to_vec(), key, &associated_data, &hex(iv));
+
+	assert_eq!(hex_string(&cipher_text[..cipher_text.len() - GCM_TAG_SIZE]), expected);
+	assert_eq!(hex_string(&cipher_text[cipher_text.len() - GCM_TAG_SIZE..]), tag);
+	assert_eq!(gcm_decrypt_with_iv(cipher_text, key, &associated_data, &hex(iv)).unwrap(), plain_text);
+}
+
+/// The 60-byte IV of test cases 6, 12 and 18.
+const LONG_IV: &str = concat!(
+	"9313225df88406e555909c5aff5269aa",
+	"6a7a9538534f7da1e4c303d2a318a728",
+	"c3c0c95156809539fcf0e2429a6b5254",
+	"16aedbf5a0de6a57a637b39b",
+);
+
+fn hex_string(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn test_case_1_empty_plaintext() {
+	check_vector([0u8; 16], "000000000000000000000000", &[], &[], "", "58e2fccefa7e3061367f1d57a4e7455a");
+}
+
+#[test]
+fn test_case_2_one_zero_block() {
+	check_vector(
+		[0u8; 16],
+		"000000000000000000000000",
+		&[0; 16],
+		&[],
+		"0388dace60b6a392f328c2b971b2fe78",
+		"ab6e47d42cec13bdf53a67b21257bddf",
+	);
+}
+
+#[test]
+fn test_case_3_four_blocks() {
+	let key: [u8; 16] = hex("feffe9928665731c6d6a8f9467308308").try_into().unwrap();
+	check_vector(
+		key,
+		NONCE,
+		&hex(PLAIN_TEXT),
+		&[],
+		concat!(
+			"42831ec2217774244b7221b784d0d49c",
+			"e3aa212f2c02a4e035c17e2329aca12e",
+			"21d514b25466931c7d8f6a5aac84aa05",
+			"1ba30b396a0aac973d58e091473f5985",
+		),
+		"4d5c2af327cd64a62cf35abd2ba6fab4",
+	);
+}
+
+#[test]
+fn test_case_4_partial_block_and_associated_data() {
+	let key: [u8; 16] = hex("feffe9928665731c6d6a8f9467308308").try_into().unwrap();
+	check_vector(
+		key,
+		NONCE,
+		&hex(PLAIN_TEXT)[..60],
+		&hex(ASSOCIATED_DATA),
+		concat!(
+			"42831ec2217774244b7221b784d0d49c",
+			"e3aa212f2c02a4e035c17e2329aca12e",
+			"21d514b25466931c7d8f6a5aac84aa05",
+			"1ba30b396a0aac973d58e091",
+		),
+		"5bc94fbc3221a5db94fae95ae7121a47",
+	);
+}
+
+#[test]
+fn test_case_5_short_iv() {
+	check_iv_vector(
+		<[u8; 16]>::try_from(hex("feffe9928665731c6d6a8f9467308308")).unwrap(),
+		"cafebabefacedbad",
+		concat!(
+			"61353b4c2806934a777ff51fa22a4755",
+			"699b2a714fcdc6f83766e5f97b6c7423",
+			"73806900e49f24b22b097544d4896b42",
+			"4989b5e1ebac0f07c23f4598",
+		),
+		"3612d2e79e3b0785561be14aaca2fccb",
+	);
+}
+
+#[test]
+fn test_case_6_long_iv() {
+	check_iv_vector(
+		<[u8; 16]>::try_from(hex("feffe9928665731c6d6a8f9467308308")).unwrap(),
+		LONG_IV,
+		concat!(
+			"8ce24998625615b603a033aca13fb894",
+			"be9112a5c3a211a8ba262a3cca7e2ca7",
+			"01e4a9a4fba43c90ccdcb281d48c7c6f",
+			"d62875d2aca417034c34aee5",
+		),
+		"619cc5aefffe0bfa462af43c1699d050",
+	);
+}
+
+#[test]
+fn test_case_12_aes192_long_iv() {
+	check_iv_vector(
+		<[u8; 24]>::try_from(hex("feffe9928665731c6d6a8f9467308308feffe9928665731c")).unwrap(),
+		LONG_IV,
+		concat!(
+			"d27e88681ce3243c4830165a8fdcf9ff",
+			"1de9a1d8e6b447ef6ef7b79828666e45",
+			"81e79012af34ddd9e2f037589b292db3",
+			"e67c036745fa22e7e9b7373b",
+		),
+		"dcf566ff291c25bbb8568fc3d376a6d9",
+	);
+}
+
+#[test]
+fn test_case_18_aes256_long_iv() {
+	check_iv_vector(
+		<[u8; 32]>::try_from(hex("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308")).unwrap(),
+		LONG_IV,
+		concat!(
+			"5a8def2f0c9e53f1f75d7853659e2a20",
+			"eeb2b22aafde6419a058ab4f6f746bf4",
+			"0fc0c3b780f244452da3ebf1c5d82cde",
+			"a2418997200ef82e44ae7e3f",
+		),
+		"a44a8266ee1c8eb0c8b5d4cf5ae9f19a",
+	);
+}
+
+#[test]
+fn a_96_bit_iv_takes_the_nonce_path() {
+	let key: [u8; 16] = hex("feffe9928665731c6d6a8f9467308308").try_into().unwrap();
+	let nonce: [u8; GCM_NONCE_SIZE] = hex(NONCE).try_into().unwrap();
+	let with_nonce = gcm_encrypt_with_nonce(hex(PLAIN_TEXT), key, &[], nonce);
+
+	assert_eq!(gcm_encrypt_with_iv(hex(PLAIN_TEXT), key, &[], &nonce), with_nonce[GCM_NONCE_SIZE..]);
+	assert_eq!(gcm_decrypt_with_iv(vec![0; GCM_TAG_SIZE], key, &[], &[]), Err(DecryptError::InvalidLength));
+}
+
+#[test]
+fn test_case_16_aes256() {
+	let key: [u8; 32] = hex("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308").try_into().unwrap();
+	check_vector(
+		key,
+		NONCE,
+		&hex(PLAIN_TEXT)[..60],
+		&hex(ASSOCIATED_DATA),
+		concat!(
+			"522dc1f099567d07f47f37a32a84427d",
+			"643a8cdcbfe5c0c97598a2bd2555d1aa",
+			"8cb08e48590dbb3da7b08b1056828838",
+			"c5f61e6393ba7a0abcc9f662",
+		),
+		"76fc6ece0f4e1768cddf8853bb2d551b",
+	);
+}
+
+#[test]
+fn any_modification_fails_authentication() {
+	let key = [7u8; 16];
+	let associated_data = b"header";
+	let cipher_text = gcm_encrypt(b"attack at dawn".to_vec(), key, associated_data);
+
+	for i in 0..cipher_text.len() {
+		let mut tampered = cipher_text.clone();
+		tampered[i] ^= 1;
+		assert_eq!(gcm_decrypt(tampered, key, associated_data), Err(DecryptError::AuthenticationFailed), "byte {i}");
+	}
+	assert_eq!(gcm_decrypt(cipher_text.clone(), key, b"Header"), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(gcm_decrypt(cipher_text.clone(), [8u8; 16], associated_data), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(gcm_decrypt(cipher_text, key, associated_data).unwrap(), b"attack at dawn");
+}
+
+#[test]
+fn verifying_agrees_with_decrypting() {
+	let key = [7u8; 16];
+	let associated_data = b"header";
+	let cipher_text = gcm_encrypt(b"attack at dawn".to_vec(), key, associated_data);
+
+	assert_eq!(gcm_verify(&cipher_text, key, associated_data), Ok(()));
+	for i in 0..cipher_text.len() {
+		let mut tampered = cipher_text.clone();
+		tampered[i] ^= 1;
+		assert_eq!(gcm_verify(&tampered, key, associated_data), Err(DecryptError::AuthenticationFailed), "byte {i}");
+	}
+	assert_eq!(gcm_verify(&cipher_text, key, b"Header"), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(gcm_verify(&cipher_text[..GCM_NONCE_SIZE + GCM_TAG_SIZE - 1], key, associated_data), Err(DecryptError::InvalidLength));
+}
+
+#[test]
+fn associated_data_lists_keep_their_field_boundaries() {
+	let key = [7u8; 16];
+	let cipher_text = gcm_encrypt_with_aad_list(b"attack at dawn".to_vec(), key, &[b"ab", b"c"]);
+
+	assert_eq!(gcm_decrypt_with_aad_list(cipher_text.clone(), key, &[b"ab", b"c"]).unwrap(), b"attack at dawn");
+	assert_eq!(gcm_decrypt_with_aad_list(cipher_text.clone(), key, &[b"a", b"bc"]), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(gcm_decrypt_with_aad_list(cipher_text.clone(), key, &[b"abc"]), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(gcm_decrypt(cipher_text, key, b"abc"), Err(DecryptError::AuthenticationFailed));
+
+	// The same nonce for both, so only the associated data differs between the tags.
+	let nonce = [0u8; GCM_NONCE_SIZE];
+	let tag = |fields: &[&[u8]]| {
+		let cipher_text = gcm_encrypt_with_nonce(Vec::new(), key, &aes_modes::encode_aad_list(fields), nonce);
+		cipher_text[GCM_NONCE_SIZE..].to_vec()
+	};
+	assert_ne!(tag(&[b"ab", b"c"]), tag(&[b"a", b"bc"]));
+}
+
+#[test]
+fn detached_tags_convert_to_and_from_the_combined_form() {
+	let key = [7u8; 16];
+	let nonce = [9u8; GCM_NONCE_SIZE];
+	let associated_data = b"header";
+	let (cipher_text, tag) = gcm_encrypt_detached(b"attack at dawn".to_vec(), key, associated_data, nonce);
+
+	// nonce || ciphertext || tag, the combined form, is the same pieces.
+	let combined = gcm_encrypt_with_nonce(b"attack at dawn".to_vec(), key, associated_data, nonce);
+	assert_eq!(combined, [&nonce[..], &cipher_text, &tag].concat());
+	assert_eq!(gcm_decrypt(combined.clone(), key, associated_data).unwrap(), b"attack at dawn");
+
+	let body = combined[GCM_NONCE_SIZE..combined.len() - GCM_TAG_SIZE].to_vec();
+	let split_tag: [u8; GCM_TAG_SIZE] = combined[combined.len() - GCM_TAG_SIZE..].try_into().unwrap();
+	assert_eq!(gcm_decrypt_detached(body, key, associated_data, nonce, &split_tag).unwrap(), b"attack at dawn");
+
+	let mut wrong_tag = tag;
+	wrong_tag[0] ^= 1;
+	assert_eq!(gcm_decrypt_detached(cipher_text.clone(), key, associated_data, nonce, &wrong_tag), Err(DecryptError::AuthenticationFailed));
+	let mut tampered = cipher_text.clone();
+	tampered[0] ^= 1;
+	assert_eq!(gcm_decrypt_detached(tampered, key, associated_data, nonce, &tag), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(gcm_decrypt_detached(cipher_text, key, b"Header", nonce, &tag), Err(DecryptError::AuthenticationFailed));
+}
+
+#[test]
+fn too_short_ciphertext_is_rejected() {
+	assert_eq!(gcm_decrypt(vec![0; GCM_NONCE_SIZE + GCM_TAG_SIZE - 1], [0u8; 16], &[]), Err(DecryptError::InvalidLength));
+}
+
+/// SP 800-38D defines a t-bit tag as the leading t bits of the full one (section 7.1,
+/// MSB_t), so the spec vectors above cut down give the truncated-tag vectors.
+#[test]
+fn truncated_tags_are_the_leading_bytes_of_the_full_tag() {
+	let key: [u8; 16] = hex("feffe9928665731c6d6a8f9467308308").try_into().unwrap();
+	let nonce: [u8; GCM_NONCE_SIZE] = hex(NONCE).try_into().unwrap();
+	let plain_text = hex(PLAIN_TEXT)[..60].to_vec();
+	let associated_data = hex(ASSOCIATED_DATA);
+	let full = gcm_encrypt_with_nonce(plain_text.clone(), key, &associated_data, nonce);
+
+	for (tag_len, tag) in [(12, "5bc94fbc3221a5db94fae95a"), (8, "5bc94fbc3221a5db"), (4, "5bc94fbc")] {
+		let truncated = full[..full.len() - GCM_TAG_SIZE + tag_len].to_vec();
+		assert_eq!(hex_string(&truncated[truncated.len() - tag_len..]), tag);
+		assert_eq!(gcm_decrypt_with_tag_len(truncated, key, &associated_data, tag_len).unwrap(), plain_text, "{tag_len}");
+
+		// A random nonce this time, checked against the full tag for the same nonce.
+		let cipher_text = gcm_encrypt_with_tag_len(plain_text.clone(), key, &associated_data, tag_len);
+		assert_eq!(cipher_text.len(), GCM_NONCE_SIZE + plain_text.len() + tag_len);
+		let nonce = cipher_text[..GCM_NONCE_SIZE].try_into().unwrap();
+		let full = gcm_encrypt_with_nonce(plain_text.clone(), key, &associated_data, nonce);
+		assert_eq!(cipher_text[..], full[..full.len() - GCM_TAG_SIZE + tag_len]);
+	}
+}
+
+/// A random 4-byte tag is right with probability 2^-32, so all of these forgeries failing
+/// is expected: the chance that even one slips through is about 2.3 in a million.
+#[test]
+fn a_four_byte_tag_still_rejects_random_forgeries() {
+	let key = [7u8; 16];
+	let cipher_text = gcm_encrypt_with_tag_len(b"attack at dawn".to_vec(), key, b"header", GCM_MIN_TAG_SIZE);
+	let body_len = cipher_text.len() - GCM_MIN_TAG_SIZE;
+	let tag: [u8; GCM_MIN_TAG_SIZE] = cipher_text[body_len..].try_into().unwrap();
+
+	for _ in 0..10_000 {
+		let forged_tag: [u8; GCM_MIN_TAG_SIZE] = rand::random();
+		if forged_tag == tag {
+			continue;
+		}
+		let mut forgery = cipher_text[..body_len].to_vec();
+		forgery.extend_from_slice(&forged_tag);
+
+		assert_eq!(
+			gcm_decrypt_with_tag_len(forgery, key, b"header", GCM_MIN_TAG_SIZE),
+			Err(DecryptError::AuthenticationFailed)
+		);
+	}
+}
+
+#[test]
+#[should_panic(expected = "GCM tags must be 4 to 16 bytes, not 3")]
+fn tags_shorter_than_four_bytes_are_refused() {
+	gcm_encrypt_with_tag_len(Vec::new(), [0u8; 16], &[], 3);
+}
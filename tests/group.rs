@@ -0,0 +1,44 @@
+use aes_modes::{group, un_group, DecryptError, BLOCK_SIZE};
+use proptest::prelude::*;
+
+#[test]
+fn misaligned_input_is_an_error_not_a_panic() {
+	for len in [1, BLOCK_SIZE - 1, BLOCK_SIZE + 1, 3 * BLOCK_SIZE - 1] {
+		assert_eq!(group(vec![0; len]), Err(DecryptError::InvalidLength), "{len}");
+	}
+}
+
+#[test]
+fn aligned_input_round_trips_through_un_group() {
+	for blocks in 0..4 {
+		let data: Vec<u8> = (0..(blocks * BLOCK_SIZE) as u8).collect();
+		let grouped = group(data.clone()).unwrap();
+
+		assert_eq!(grouped.len(), blocks);
+		assert_eq!(un_group(grouped), data);
+	}
+}
+
+#[test]
+fn empty_input_groups_to_no_blocks() {
+	let grouped = group(Vec::new()).unwrap();
+
+	assert!(grouped.is_empty());
+	assert!(un_group(grouped).is_empty());
+}
+
+#[test]
+fn one_block_groups_to_exactly_that_block() {
+	let data: Vec<u8> = (0..BLOCK_SIZE as u8).collect();
+	let grouped = group(data.clone()).unwrap();
+
+	assert_eq!(grouped, [<[u8; BLOCK_SIZE]>::try_from(data.as_slice()).unwrap()]);
+	assert_eq!(un_group(grouped), data);
+}
+
+proptest! {
+	#[test]
+	fn group_then_un_group_is_the_identity(data in (0..64usize).prop_flat_map(|blocks| prop::collection::vec(any::<u8>(), blocks * BLOCK_SIZE))) {
+		prop_assert_eq!(un_group(group(data.clone()).unwrap()), data);
+	}
+}
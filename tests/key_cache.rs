@@ -0,0 +1,37 @@
+//! The `key-cache` feature must only change how often keys are expanded, never the output.
+#![cfg(feature = "key-cache")]
+
+use aes::{
+	cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit},
+	Aes128,
+};
+use aes_modes::{ecb_decrypt, ecb_encrypt, BLOCK_SIZE};
+
+/// ECB straight from the `aes` crate, with a freshly expanded key and no cache involved.
+fn reference_ecb(message: &[u8], key: [u8; BLOCK_SIZE]) -> Vec<u8> {
+	let cipher = Aes128::new(&GenericArray::from(key));
+
+	aes_modes::pad(message.to_vec())
+		.chunks_exact(BLOCK_SIZE)
+		.flat_map(|block| {
+			let mut block = GenericArray::clone_from_slice(block);
+			cipher.encrypt_block(&mut block);
+			block
+		})
+		.collect()
+}
+
+#[test]
+fn cache_hits_and_misses_give_the_same_ciphertext() {
+	let a = *b"YELLOW SUBMARINE";
+	let b = *b"ORANGE SUBMARINE";
+	let message = b"a message that spans a few blocks of plaintext".to_vec();
+
+	// A misses, A hits, B replaces it, A misses again.
+	for key in [a, a, b, a] {
+		let cipher_text = ecb_encrypt(message.clone(), key);
+
+		assert_eq!(cipher_text, reference_ecb(&message, key));
+		assert_eq!(ecb_decrypt(cipher_text, key).unwrap(), message);
+	}
+}
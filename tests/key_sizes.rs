@@ -0,0 +1,58 @@
+mod common;
+
+use aes_modes::{cbc_decrypt, cbc_encrypt, ctr_decrypt, ctr_encrypt, ecb_decrypt, ecb_encrypt, BLOCK_SIZE};
+use common::hex;
+
+const PLAIN_TEXT: &str = concat!(
+	"6bc1bee22e409f96e93d7e117393172a",
+	"ae2d8a571e03ac9c9eb76fac45af8e51",
+	"30c81c46a35ce411e5fbc1191a0a52ef",
+	"f69f2445df4f9b17ad2b417be66c3710",
+);
+
+/// NIST SP 800-38A, F.1.5 ECB-AES256.Encrypt.
+#[test]
+fn ecb_matches_the_nist_aes256_vector() {
+	let key: [u8; 32] = hex("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4").try_into().unwrap();
+	let expected = hex(concat!(
+		"f3eed1bdb5d2a03c064b5a7e3db181f8",
+		"591ccb10d410ed26dc5ba74a31362870",
+		"b6ed21b99ca6f4f9f153e7b1beafed1d",
+		"23304b7a39f9f3ff067d8d8f9e24ecc7",
+	));
+
+	let cipher_text = ecb_encrypt(hex(PLAIN_TEXT), key);
+
+	// Our ECB adds a full padding block after the four vector blocks.
+	assert_eq!(cipher_text.len(), expected.len() + BLOCK_SIZE);
+	assert_eq!(cipher_text[..expected.len()], expected);
+	assert_eq!(ecb_decrypt(cipher_text, key).unwrap(), hex(PLAIN_TEXT));
+}
+
+/// NIST SP 800-38A, F.1.3 ECB-AES192.Encrypt, first block.
+#[test]
+fn ecb_matches_the_nist_aes192_vector() {
+	let key: [u8; 24] = hex("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b").try_into().unwrap();
+
+	let cipher_text = ecb_encrypt(hex(&PLAIN_TEXT[..2 * BLOCK_SIZE]), key);
+
+	assert_eq!(cipher_text[..BLOCK_SIZE], hex("bd334f1d6e45f25ff712a214571fa5cc"));
+}
+
+#[test]
+fn every_mode_round_trips_with_a_256_bit_key() {
+	let key = [0x5a; 32];
+	let message = b"a message that spans a few blocks of plaintext".to_vec();
+
+	assert_eq!(ecb_decrypt(ecb_encrypt(message.clone(), key), key).unwrap(), message);
+	assert_eq!(cbc_decrypt(cbc_encrypt(message.clone(), key), key).unwrap(), message);
+	assert_eq!(ctr_decrypt(ctr_encrypt(message.clone(), key), key).unwrap(), message);
+}
+
+#[test]
+fn the_key_size_changes_the_ciphertext() {
+	let message = b"exactly sixteen!".to_vec();
+
+	assert_ne!(ecb_encrypt(message.clone(), [0; 16]), ecb_encrypt(message.clone(), [0; 32]));
+	assert_ne!(ecb_encrypt(message.clone(), [0; 24]), ecb_encrypt(message, [0; 32]));
+}
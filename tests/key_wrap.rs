@@ -0,0 +1,127 @@
+mod common;
+
+use aes_modes::{aes_unwrap, aes_wrap, ctr_decrypt, ctr_encrypt, rewrap, AesKey, DecryptError};
+use common::hex;
+
+const KEK_128: &str = "000102030405060708090a0b0c0d0e0f";
+const KEK_192: &str = "000102030405060708090a0b0c0d0e0f1011121314151617";
+const KEK_256: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+const KEY_DATA_128: &str = "00112233445566778899aabbccddeeff";
+const KEY_DATA_192: &str = "00112233445566778899aabbccddeeff0001020304050607";
+const KEY_DATA_256: &str = "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f";
+
+/// Runs one vector from RFC 3394 section 4, in both directions.
+fn check_vector<K: AesKey + Copy>(kek: K, key_data: &str, expected: &str) {
+	let wrapped = aes_wrap(kek, &hex(key_data));
+
+	assert_eq!(wrapped, hex(expected));
+	assert_eq!(aes_unwrap(kek, &wrapped).unwrap(), hex(key_data));
+}
+
+fn kek<const N: usize>(kek: &str) -> [u8; N] {
+	hex(kek).try_into().unwrap()
+}
+
+#[test]
+fn rfc_3394_4_1_128_bit_key_data_with_a_128_bit_kek() {
+	check_vector(kek::<16>(KEK_128), KEY_DATA_128, "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5");
+}
+
+#[test]
+fn rfc_3394_4_2_128_bit_key_data_with_a_192_bit_kek() {
+	check_vector(kek::<24>(KEK_192), KEY_DATA_128, "96778b25ae6ca435f92b5b97c050aed2468ab8a17ad84e5d");
+}
+
+#[test]
+fn rfc_3394_4_3_128_bit_key_data_with_a_256_bit_kek() {
+	check_vector(kek::<32>(KEK_256), KEY_DATA_128, "64e8c3f9ce0f5ba263e9777905818a2a93c8191e7d6e8ae7");
+}
+
+#[test]
+fn rfc_3394_4_4_192_bit_key_data_with_a_192_bit_kek() {
+	check_vector(
+		kek::<24>(KEK_192),
+		KEY_DATA_192,
+		"031d33264e15d33268f24ec260743edce1c6c7ddee725a936ba814915c6762d2",
+	);
+}
+
+#[test]
+fn rfc_3394_4_5_192_bit_key_data_with_a_256_bit_kek() {
+	check_vector(
+		kek::<32>(KEK_256),
+		KEY_DATA_192,
+		"a8f9bc1612c68b3ff6e6f4fbe30e71e4769c8b80a32cb8958cd5d17d6b254da1",
+	);
+}
+
+#[test]
+fn rfc_3394_4_6_256_bit_key_data_with_a_256_bit_kek() {
+	check_vector(
+		kek::<32>(KEK_256),
+		KEY_DATA_256,
+		"28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21",
+	);
+}
+
+#[test]
+fn unwrapping_with_the_wrong_kek_fails() {
+	let wrapped = aes_wrap(kek::<16>(KEK_128), &hex(KEY_DATA_128));
+	let mut wrong = kek::<16>(KEK_128);
+	wrong[0] ^= 1;
+
+	assert_eq!(aes_unwrap(wrong, &wrapped), Err(DecryptError::AuthenticationFailed));
+	// The same bytes as a different key size are a different key too.
+	assert_eq!(aes_unwrap(kek::<24>(KEK_192), &wrapped), Err(DecryptError::AuthenticationFailed));
+}
+
+#[test]
+fn unwrapping_a_modified_wrapped_key_fails() {
+	let wrapped = aes_wrap(kek::<32>(KEK_256), &hex(KEY_DATA_256));
+
+	for i in 0..wrapped.len() {
+		let mut tampered = wrapped.clone();
+		tampered[i] ^= 0x80;
+
+		assert_eq!(aes_unwrap(kek::<32>(KEK_256), &tampered), Err(DecryptError::AuthenticationFailed), "byte {i}");
+	}
+}
+
+#[test]
+fn unwrapping_a_bad_length_fails() {
+	let kek = kek::<16>(KEK_128);
+	let wrapped = aes_wrap(kek, &hex(KEY_DATA_128));
+
+	// Shorter than A plus two semiblocks, or not a whole number of semiblocks.
+	for bad in [&[][..], &wrapped[..8], &wrapped[..16], &wrapped[..23], &[wrapped.as_slice(), &[0]].concat()] {
+		assert_eq!(aes_unwrap(kek, bad), Err(DecryptError::InvalidLength), "{} bytes", bad.len());
+	}
+}
+
+#[test]
+#[should_panic(expected = "key wrap input must be at least 16 bytes")]
+fn wrapping_less_than_two_semiblocks_panics() {
+	aes_wrap(kek::<16>(KEK_128), &[0; 8]);
+}
+
+#[test]
+fn rewrapping_moves_the_data_key_to_the_new_master_key() {
+	let old_master = [0x11u8; 16];
+	let new_master = [0x22u8; 32];
+	let data_key = [0x33u8; 16];
+
+	let payload = ctr_encrypt(b"a large payload encrypted under the data key".to_vec(), data_key);
+	let wrapped = aes_wrap(old_master, &data_key);
+
+	let rewrapped = rewrap(old_master, new_master, &wrapped).unwrap();
+	assert_ne!(rewrapped, wrapped);
+	assert_eq!(rewrapped, aes_wrap(new_master, &data_key));
+
+	let recovered: [u8; 16] = aes_unwrap(new_master, &rewrapped).unwrap().try_into().unwrap();
+	assert_eq!(ctr_decrypt(payload, recovered).unwrap(), b"a large payload encrypted under the data key");
+
+	assert_eq!(aes_unwrap(old_master, &rewrapped), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(rewrap([0x44u8; 16], new_master, &wrapped), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(rewrap(old_master, new_master, &wrapped[1..]), Err(DecryptError::InvalidLength));
+}
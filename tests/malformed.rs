@@ -0,0 +1,103 @@
+//! Malformed ciphertext must come back as a `DecryptError`, never a panic.
+
+use aes_modes::{
+	cbc_decrypt, cbc_decrypt_bounded, cbc_decrypt_uniform, cbc_encrypt, ctr_decrypt, ctr_decrypt_bounded, ctr_encrypt, ecb_decrypt,
+	ecb_decrypt_bounded, ecb_encrypt, DecryptError, BLOCK_SIZE,
+};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+type Decrypt = fn(Vec<u8>, [u8; BLOCK_SIZE]) -> Result<Vec<u8>, DecryptError>;
+
+const DECRYPTS: [(&str, Decrypt); 3] = [("ecb", ecb_decrypt), ("cbc", cbc_decrypt), ("ctr", ctr_decrypt)];
+
+#[test]
+fn empty_ciphertext_is_rejected() {
+	for (mode, decrypt) in DECRYPTS {
+		assert_eq!(decrypt(Vec::new(), KEY), Err(DecryptError::Empty), "{mode}");
+	}
+}
+
+#[test]
+fn an_iv_with_no_blocks_after_it_is_rejected() {
+	assert_eq!(cbc_decrypt(vec![0; BLOCK_SIZE], KEY), Err(DecryptError::Empty));
+}
+
+#[test]
+fn truncated_ciphertext_is_rejected() {
+	let cipher_text = cbc_encrypt(b"a message that spans a few blocks of plaintext".to_vec(), KEY);
+
+	// CTR isn't padded, so any length past the nonce is a valid ciphertext. It only has to be
+	// checked for being shorter than the nonce.
+	for len in [1, BLOCK_SIZE - 1, BLOCK_SIZE + 1, cipher_text.len() - 1] {
+		for (mode, decrypt) in &DECRYPTS[..2] {
+			assert_eq!(decrypt(cipher_text[..len].to_vec(), KEY), Err(DecryptError::InvalidLength), "{mode} {len}");
+		}
+	}
+	for len in 1..BLOCK_SIZE / 2 {
+		assert_eq!(ctr_decrypt(vec![0; len], KEY), Err(DecryptError::InvalidLength), "ctr {len}");
+	}
+}
+
+#[test]
+fn zero_padding_is_rejected() {
+	// ECB is deterministic, so a block of zeros can be encrypted on its own and passed off as
+	// the final block.
+	let zero_block = ecb_encrypt(vec![0; BLOCK_SIZE], KEY)[..BLOCK_SIZE].to_vec();
+
+	assert_eq!(ecb_decrypt(zero_block, KEY), Err(DecryptError::InvalidPadding));
+}
+
+#[test]
+fn an_oversized_pad_count_is_rejected() {
+	let mut plain_text = vec![0x42; BLOCK_SIZE];
+	plain_text[BLOCK_SIZE - 1] = BLOCK_SIZE as u8 + 1;
+	let forged = ecb_encrypt(plain_text, KEY)[..BLOCK_SIZE].to_vec();
+
+	assert_eq!(ecb_decrypt(forged, KEY), Err(DecryptError::InvalidPadding));
+}
+
+#[test]
+fn bounded_decrypts_refuse_ciphertext_that_could_exceed_the_limit() {
+	let message = vec![0x42; 40];
+	let ecb = ecb_encrypt(message.clone(), KEY);
+	let cbc = cbc_encrypt(message.clone(), KEY);
+	let ctr = ctr_encrypt(message.clone(), KEY);
+
+	// 48 bytes of ECB ciphertext could hold up to 47 of plaintext, however much is padding.
+	assert_eq!(ecb_decrypt_bounded(ecb.clone(), KEY, 47).unwrap(), message);
+	assert_eq!(ecb_decrypt_bounded(ecb, KEY, 46), Err(DecryptError::OutputTooLarge));
+	assert_eq!(cbc_decrypt_bounded(cbc.clone(), KEY, 47).unwrap(), message);
+	assert_eq!(cbc_decrypt_bounded(cbc, KEY, 46), Err(DecryptError::OutputTooLarge));
+	assert_eq!(ctr_decrypt_bounded(ctr.clone(), KEY, 40).unwrap(), message);
+	assert_eq!(ctr_decrypt_bounded(ctr, KEY, 39), Err(DecryptError::OutputTooLarge));
+
+	// Malformed input within the limit still gets the usual errors.
+	assert_eq!(ecb_decrypt_bounded(Vec::new(), KEY, 0), Err(DecryptError::Empty));
+	assert_eq!(cbc_decrypt_bounded(vec![0; BLOCK_SIZE + 1], KEY, 64), Err(DecryptError::InvalidLength));
+}
+
+#[test]
+fn uniform_cbc_errors_look_the_same_whatever_went_wrong() {
+	let message = b"a message that spans a few blocks of plaintext".to_vec();
+	let cipher_text = cbc_encrypt(message.clone(), KEY);
+	assert_eq!(cbc_decrypt_uniform(cipher_text.clone(), KEY).unwrap(), message);
+
+	let mut bad_padding = cipher_text.clone();
+	let last = bad_padding.len() - BLOCK_SIZE - 1;
+	bad_padding[last] ^= 0xff;
+	assert_eq!(cbc_decrypt(bad_padding.clone(), KEY), Err(DecryptError::InvalidPadding));
+
+	let bad_length = cipher_text[..cipher_text.len() - 1].to_vec();
+	assert_eq!(cbc_decrypt(bad_length.clone(), KEY), Err(DecryptError::InvalidLength));
+
+	for (name, input) in [
+		("bad padding", bad_padding),
+		("bad length", bad_length),
+		("IV only", cipher_text[..BLOCK_SIZE].to_vec()),
+		("short", vec![0; 3]),
+		("empty", Vec::new()),
+	] {
+		assert_eq!(cbc_decrypt_uniform(input, KEY), Err(DecryptError::Failed), "{name}");
+	}
+}
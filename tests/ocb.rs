@@ -0,0 +1,222 @@
+mod common;
+
+use aes_modes::{
+	ocb_decrypt, ocb_decrypt_with_aad_list, ocb_encrypt, ocb_encrypt_with_aad_list, AesKey, DecryptError, OCB_NONCE_SIZE,
+	OCB_TAG_SIZE,
+};
+use common::hex;
+
+/// The appendix A key, 000102...0f.
+const KEY: [u8; 16] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f];
+
+/// Runs one vector from RFC 7253 appendix A: nonce BBAA998877665544332211xx, with A and P
+/// each taken as the first `a_len` and `p_len` bytes of 00 01 02 ...
+fn check_vector(nonce_low_byte: u8, a_len: u8, p_len: u8, expected: &str) {
+	let mut nonce: [u8; OCB_NONCE_SIZE] = hex("bbaa99887766554433221100").try_into().unwrap();
+	nonce[OCB_NONCE_SIZE - 1] = nonce_low_byte;
+	let associated_data: Vec<u8> = (0..a_len).collect();
+	let plain_text: Vec<u8> = (0..p_len).collect();
+
+	let cipher_text = ocb_encrypt(KEY, &nonce, &associated_data, &plain_text);
+
+	assert_eq!(cipher_text, hex(expected), "nonce {nonce_low_byte:#04x}");
+	assert_eq!(ocb_decrypt(KEY, &nonce, &associated_data, &cipher_text).unwrap(), plain_text);
+}
+
+#[test]
+fn rfc_7253_empty() {
+	check_vector(0x00, 0, 0, "785407bfffc8ad9edcc5520ac9111ee6");
+}
+
+#[test]
+fn rfc_7253_8_bytes() {
+	check_vector(0x01, 8, 8, "6820b3657b6f615a5725bda0d3b4eb3a257c9af1f8f03009");
+	check_vector(0x02, 8, 0, "81017f8203f081277152fade694a0a00");
+	check_vector(0x03, 0, 8, "45dd69f8f5aae72414054cd1f35d82760b2cd00d2f99bfa9");
+}
+
+#[test]
+fn rfc_7253_16_bytes() {
+	check_vector(0x04, 16, 16, "571d535b60b277188be5147170a9a22c3ad7a4ff3835b8c5701c1ccec8fc3358");
+	check_vector(0x05, 16, 0, "8cf761b6902ef764462ad86498ca6b97");
+	check_vector(0x06, 0, 16, "5ce88ec2e0692706a915c00aeb8b2396f40e1c743f52436bdf06d8fa1eca343d");
+}
+
+#[test]
+fn rfc_7253_24_bytes() {
+	check_vector(
+		0x07,
+		24,
+		24,
+		"1ca2207308c87c010756104d8840ce1952f09673a448a122c92c62241051f57356d7f3c90bb0e07f",
+	);
+	check_vector(0x08, 24, 0, "6dc225a071fc1b9f7c69f93b0f1e10de");
+	check_vector(
+		0x09,
+		0,
+		24,
+		"221bd0de7fa6fe993eccd769460a0af2d6cded0c395b1c3ce725f32494b9f914d85c0b1eb38357ff",
+	);
+}
+
+#[test]
+fn rfc_7253_32_bytes() {
+	check_vector(
+		0x0a,
+		32,
+		32,
+		"bd6f6c496201c69296c11efd138a467abd3c707924b964deaffc40319af5a48540fbba186c5553c68ad9f592a79a4240",
+	);
+	check_vector(0x0b, 32, 0, "fe80690bee8a485d11f32965bc9d2a32");
+	check_vector(
+		0x0c,
+		0,
+		32,
+		"2942bfc773bda23cabc6acfd9bfd5835bd300f0973792ef46040c53f1432bcdfb5e1dde3bc18a5f840b52e653444d5df",
+	);
+}
+
+#[test]
+fn rfc_7253_40_bytes() {
+	check_vector(
+		0x0d,
+		40,
+		40,
+		"d5ca91748410c1751ff8a2f618255b68a0a12e093ff454606e59f9c1d0ddc54b65e8628e568bad7aed07ba06a4a69483a7035490c5769e60",
+	);
+	check_vector(0x0e, 40, 0, "c5cd9d1850c141e358649994ee701b68");
+	check_vector(
+		0x0f,
+		0,
+		40,
+		"4412923493c57d5de0d700f753cce0d1d2d95060122e9f15a5ddbfc5787e50b5cc55ee507bcb084e479ad363ac366b95a98ca5f3000b1479",
+	);
+}
+
+/// The iterated vector from RFC 7253 appendix A, which runs lengths 0 through 127 through
+/// every combination of A and P and authenticates all the output at once. The key is all
+/// zeros except for a last byte of 128, the tag length.
+fn iterated_tag<K: AesKey + Copy>(key: K) -> Vec<u8> {
+	let nonce = |n: u32| -> [u8; OCB_NONCE_SIZE] {
+		let mut nonce = [0; OCB_NONCE_SIZE];
+		nonce[OCB_NONCE_SIZE - 4..].copy_from_slice(&n.to_be_bytes());
+		nonce
+	};
+
+	let mut output = Vec::new();
+	for i in 0..128u32 {
+		let s = vec![0; i as usize];
+		output.extend(ocb_encrypt(key, &nonce(3 * i + 1), &s, &s));
+		output.extend(ocb_encrypt(key, &nonce(3 * i + 2), &[], &s));
+		output.extend(ocb_encrypt(key, &nonce(3 * i + 3), &s, &[]));
+	}
+
+	ocb_encrypt(key, &nonce(385), &output, &[])
+}
+
+fn iterated_key<const N: usize>() -> [u8; N] {
+	let mut key = [0; N];
+	key[N - 1] = 128;
+	key
+}
+
+#[test]
+fn rfc_7253_all_lengths_up_to_127() {
+	assert_eq!(iterated_tag(iterated_key::<16>()), hex("67e944d23256c5e0b6c61fa22fdf1ea2"));
+}
+
+#[test]
+fn rfc_7253_all_lengths_up_to_127_with_aes192() {
+	assert_eq!(iterated_tag(iterated_key::<24>()), hex("f673f2c3e7174aae7bae986ca9f29e17"));
+}
+
+#[test]
+fn rfc_7253_all_lengths_up_to_127_with_aes256() {
+	assert_eq!(iterated_tag(iterated_key::<32>()), hex("d90eb8e9c977c88b79dd793d7ffa161c"));
+}
+
+const NONCE: [u8; OCB_NONCE_SIZE] = *b"twelve bytes";
+const ASSOCIATED_DATA: &[u8] = b"header";
+const MESSAGE: &[u8] = b"a message that spans a few blocks of plaintext";
+
+fn encrypted() -> Vec<u8> {
+	ocb_encrypt(KEY, &NONCE, ASSOCIATED_DATA, MESSAGE)
+}
+
+#[test]
+fn a_modified_ciphertext_or_tag_fails_to_decrypt() {
+	let cipher_text = encrypted();
+
+	// Every byte of the body, including the partial last block, and every byte of the tag.
+	for i in 0..cipher_text.len() {
+		let mut tampered = cipher_text.clone();
+		tampered[i] ^= 1;
+
+		assert_eq!(
+			ocb_decrypt(KEY, &NONCE, ASSOCIATED_DATA, &tampered),
+			Err(DecryptError::AuthenticationFailed),
+			"byte {i}"
+		);
+	}
+}
+
+#[test]
+fn modified_associated_data_fails_to_decrypt() {
+	let cipher_text = encrypted();
+
+	for i in 0..ASSOCIATED_DATA.len() {
+		let mut tampered = ASSOCIATED_DATA.to_vec();
+		tampered[i] ^= 1;
+
+		assert_eq!(ocb_decrypt(KEY, &NONCE, &tampered, &cipher_text), Err(DecryptError::AuthenticationFailed), "byte {i}");
+	}
+	assert_eq!(ocb_decrypt(KEY, &NONCE, &[], &cipher_text), Err(DecryptError::AuthenticationFailed));
+}
+
+#[test]
+fn a_different_nonce_or_key_fails_to_decrypt() {
+	let cipher_text = encrypted();
+
+	for i in 0..OCB_NONCE_SIZE {
+		let mut nonce = NONCE;
+		nonce[i] ^= 1;
+
+		assert_eq!(ocb_decrypt(KEY, &nonce, ASSOCIATED_DATA, &cipher_text), Err(DecryptError::AuthenticationFailed), "byte {i}");
+	}
+	assert_eq!(
+		ocb_decrypt(*b"YELLOW SUBMARINE", &NONCE, ASSOCIATED_DATA, &cipher_text),
+		Err(DecryptError::AuthenticationFailed)
+	);
+}
+
+#[test]
+fn input_shorter_than_the_tag_is_invalid() {
+	let cipher_text = encrypted();
+
+	for len in 0..OCB_TAG_SIZE {
+		assert_eq!(ocb_decrypt(KEY, &NONCE, ASSOCIATED_DATA, &cipher_text[..len]), Err(DecryptError::InvalidLength), "{len} bytes");
+	}
+}
+
+#[test]
+fn ciphertext_is_the_message_length_plus_the_tag() {
+	for len in [0, 1, 15, 16, 17, 47] {
+		let message = vec![0x42; len];
+		let cipher_text = ocb_encrypt(KEY, &NONCE, &[], &message);
+
+		assert_eq!(cipher_text.len(), len + OCB_TAG_SIZE, "{len}");
+		assert_eq!(ocb_decrypt(KEY, &NONCE, &[], &cipher_text).unwrap(), message, "{len}");
+	}
+}
+
+#[test]
+fn associated_data_lists_keep_their_field_boundaries() {
+	let message = b"attack at dawn";
+	let cipher_text = ocb_encrypt_with_aad_list(KEY, &NONCE, &[b"ab", b"c"], message);
+
+	assert_eq!(ocb_decrypt_with_aad_list(KEY, &NONCE, &[b"ab", b"c"], &cipher_text).unwrap(), message);
+	assert_ne!(cipher_text, ocb_encrypt_with_aad_list(KEY, &NONCE, &[b"a", b"bc"], message));
+	assert_eq!(ocb_decrypt_with_aad_list(KEY, &NONCE, &[b"a", b"bc"], &cipher_text), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(ocb_decrypt_with_aad_list(KEY, &NONCE, &[b"abc"], &cipher_text), Err(DecryptError::AuthenticationFailed));
+	assert_eq!(ocb_decrypt_with_aad_list(KEY, &NONCE, &[b"ab", b"c", b""], &cipher_text), Err(DecryptError::AuthenticationFailed));
+}
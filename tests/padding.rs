@@ -0,0 +1,109 @@
+use aes_modes::{cbc_decrypt, cbc_encrypt, ecb_decrypt, ecb_encrypt, group, pad, un_group, un_pad, DecryptError, BLOCK_SIZE};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+#[test]
+fn a_whole_block_message_gets_a_full_block_of_padding() {
+	for blocks in 1..4 {
+		let message = vec![0x42; blocks * BLOCK_SIZE];
+		let padded = pad(message.clone());
+
+		assert_eq!(padded.len(), message.len() + BLOCK_SIZE);
+		assert_eq!(&padded[message.len()..], &[BLOCK_SIZE as u8; BLOCK_SIZE]);
+		assert_eq!(un_pad(padded).unwrap(), message);
+	}
+}
+
+#[test]
+fn un_pad_removes_exactly_the_pad_count() {
+	for pad_len in 1..BLOCK_SIZE {
+		let message: Vec<u8> = (0..(BLOCK_SIZE - pad_len) as u8).collect();
+		let padded = pad(message.clone());
+
+		assert_eq!(padded.len(), BLOCK_SIZE);
+		assert_eq!(&padded[message.len()..], vec![pad_len as u8; pad_len]);
+		assert_eq!(un_pad(padded).unwrap(), message);
+	}
+}
+
+#[test]
+fn un_pad_keeps_data_bytes_that_look_like_padding() {
+	// The data ends in 0x01, which is also a valid pad count. Only the real padding goes.
+	let message = vec![0x01; BLOCK_SIZE - 1];
+
+	assert_eq!(un_pad(pad(message.clone())).unwrap(), message);
+}
+
+#[test]
+fn un_pad_rejects_impossible_pad_counts() {
+	let mut zero = vec![0x42; BLOCK_SIZE];
+	zero[BLOCK_SIZE - 1] = 0;
+	let mut too_big = vec![0x42; 2 * BLOCK_SIZE];
+	too_big[2 * BLOCK_SIZE - 1] = BLOCK_SIZE as u8 + 1;
+
+	assert_eq!(un_pad(zero), Err(DecryptError::InvalidPadding));
+	assert_eq!(un_pad(too_big), Err(DecryptError::InvalidPadding));
+	assert_eq!(un_pad(vec![4; 3]), Err(DecryptError::InvalidPadding));
+}
+
+#[test]
+fn un_pad_checks_every_padding_byte() {
+	// A single wrong byte anywhere in the padding must be caught, not just in the position a
+	// short-circuiting check happens to look at first.
+	for pad_len in 2..=BLOCK_SIZE {
+		for wrong in 1..pad_len {
+			let mut padded = pad(vec![0x42; BLOCK_SIZE - pad_len]);
+			padded[BLOCK_SIZE - 1 - wrong] ^= 0x80;
+
+			assert_eq!(un_pad(padded), Err(DecryptError::InvalidPadding), "pad {pad_len}, byte {wrong}");
+		}
+	}
+}
+
+#[test]
+fn un_pad_accepts_a_count_that_matches_the_data_before_it() {
+	// Three bytes of 0x03 are valid padding even if the byte before them is also 0x03.
+	let padded = vec![0x03; BLOCK_SIZE];
+
+	assert_eq!(un_pad(padded).unwrap(), vec![0x03; BLOCK_SIZE - 3]);
+}
+
+#[test]
+fn decrypted_plaintext_has_no_spare_capacity() {
+	// Up to a whole block of padding comes off, and none of it should stay allocated.
+	for len in [0, 1, BLOCK_SIZE - 1, BLOCK_SIZE, 3 * BLOCK_SIZE + 5] {
+		let message = vec![0x42; len];
+
+		let plain_text = ecb_decrypt(ecb_encrypt(message.clone(), KEY), KEY).unwrap();
+		assert_eq!(plain_text, message);
+		assert_eq!(plain_text.capacity(), plain_text.len(), "ecb, {len} bytes");
+
+		let plain_text = cbc_decrypt(cbc_encrypt(message.clone(), KEY), KEY).unwrap();
+		assert_eq!(plain_text, message);
+		assert_eq!(plain_text.capacity(), plain_text.len(), "cbc, {len} bytes");
+	}
+}
+
+/// The pad counts where an off-by-one in un_pad shows up: a full block of padding, where
+/// removing one byte too many eats into the previous block, and a single pad byte, where
+/// removing one too few leaves it behind. In a debug build, group checks its own output and
+/// ecb_decrypt and cbc_decrypt check the plaintext length against the ciphertext length, so
+/// such a bug fails inside them as well as on the asserts here.
+#[test]
+fn pad_count_boundaries_pass_the_debug_assertions() {
+	for len in [BLOCK_SIZE, 2 * BLOCK_SIZE, BLOCK_SIZE - 1, 2 * BLOCK_SIZE - 1] {
+		let message: Vec<u8> = (0..len as u8).collect();
+
+		// First through the decrypts, so it is their assertions that catch a bug.
+		assert_eq!(ecb_decrypt(ecb_encrypt(message.clone(), KEY), KEY).unwrap(), message);
+		assert_eq!(cbc_decrypt(cbc_encrypt(message.clone(), KEY), KEY).unwrap(), message);
+
+		let padded = pad(message.clone());
+		let blocks = group(padded.clone()).unwrap();
+		assert_eq!(un_group(blocks), padded);
+
+		let plain_text = un_pad(padded).unwrap();
+		assert_eq!(plain_text.len(), len);
+		assert_eq!(plain_text, message);
+	}
+}
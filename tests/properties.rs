@@ -0,0 +1,102 @@
+//! Round-trip and length properties of every mode, over random plaintexts and keys.
+
+use aes_modes::{
+	aes_unwrap, aes_wrap, cbc_decrypt, cbc_encrypt, ctr_decrypt, ctr_encrypt, ecb_decrypt, ecb_encrypt, gcm_decrypt,
+	gcm_encrypt, ocb_decrypt, ocb_encrypt, BLOCK_SIZE, GCM_NONCE_SIZE, GCM_TAG_SIZE, OCB_NONCE_SIZE, OCB_TAG_SIZE,
+};
+use proptest::prelude::*;
+
+/// Random lengths up to a few blocks, plus the edge cases around block boundaries.
+fn plain_text() -> impl Strategy<Value = Vec<u8>> {
+	let edge_lengths = prop_oneof![Just(0), Just(BLOCK_SIZE), (0..8usize).prop_map(|k| k * BLOCK_SIZE + 1)];
+	let length = prop_oneof![edge_lengths, 0..8 * BLOCK_SIZE];
+
+	length.prop_flat_map(|len| prop::collection::vec(any::<u8>(), len))
+}
+
+/// Key data key wrap accepts: two or more 64-bit semiblocks.
+fn key_data() -> impl Strategy<Value = Vec<u8>> {
+	(2..8usize).prop_flat_map(|semiblocks| prop::collection::vec(any::<u8>(), semiblocks * BLOCK_SIZE / 2))
+}
+
+/// The padded length ECB and CBC produce: always at least one byte, up to a whole block, of
+/// padding.
+fn padded_len(len: usize) -> usize {
+	(len / BLOCK_SIZE + 1) * BLOCK_SIZE
+}
+
+proptest! {
+	#[test]
+	fn ecb_round_trips(message in plain_text(), key in any::<[u8; 16]>()) {
+		let cipher_text = ecb_encrypt(message.clone(), key);
+
+		prop_assert_eq!(cipher_text.len(), padded_len(message.len()));
+		prop_assert_eq!(ecb_decrypt(cipher_text, key), Ok(message));
+	}
+
+	#[test]
+	fn cbc_round_trips(message in plain_text(), key in any::<[u8; 16]>()) {
+		let cipher_text = cbc_encrypt(message.clone(), key);
+
+		prop_assert_eq!(cipher_text.len(), BLOCK_SIZE + padded_len(message.len()));
+		prop_assert_eq!(cbc_decrypt(cipher_text, key), Ok(message));
+	}
+
+	#[test]
+	fn ctr_round_trips(message in plain_text(), key in any::<[u8; 16]>()) {
+		let cipher_text = ctr_encrypt(message.clone(), key);
+
+		prop_assert_eq!(cipher_text.len(), BLOCK_SIZE / 2 + message.len());
+		prop_assert_eq!(ctr_decrypt(cipher_text, key), Ok(message));
+	}
+
+	#[test]
+	fn gcm_round_trips(message in plain_text(), associated_data in plain_text(), key in any::<[u8; 16]>()) {
+		let cipher_text = gcm_encrypt(message.clone(), key, &associated_data);
+
+		prop_assert_eq!(cipher_text.len(), GCM_NONCE_SIZE + message.len() + GCM_TAG_SIZE);
+		prop_assert_eq!(gcm_decrypt(cipher_text, key, &associated_data), Ok(message));
+	}
+
+	#[test]
+	fn ocb_round_trips(
+		message in plain_text(),
+		associated_data in plain_text(),
+		key in any::<[u8; 16]>(),
+		nonce in any::<[u8; OCB_NONCE_SIZE]>(),
+	) {
+		let cipher_text = ocb_encrypt(key, &nonce, &associated_data, &message);
+
+		prop_assert_eq!(cipher_text.len(), message.len() + OCB_TAG_SIZE);
+		prop_assert_eq!(ocb_decrypt(key, &nonce, &associated_data, &cipher_text), Ok(message));
+	}
+
+	#[test]
+	fn key_wrap_round_trips(key_data in key_data(), kek in any::<[u8; 16]>()) {
+		let wrapped = aes_wrap(kek, &key_data);
+
+		prop_assert_eq!(wrapped.len(), key_data.len() + BLOCK_SIZE / 2);
+		prop_assert_eq!(aes_unwrap(kek, &wrapped), Ok(key_data));
+	}
+
+	#[test]
+	fn every_mode_round_trips_with_aes256(message in plain_text(), key in any::<[u8; 32]>()) {
+		prop_assert_eq!(ecb_decrypt(ecb_encrypt(message.clone(), key), key), Ok(message.clone()));
+		prop_assert_eq!(cbc_decrypt(cbc_encrypt(message.clone(), key), key), Ok(message.clone()));
+		prop_assert_eq!(ctr_decrypt(ctr_encrypt(message.clone(), key), key), Ok(message.clone()));
+		prop_assert_eq!(gcm_decrypt(gcm_encrypt(message.clone(), key, &[]), key, &[]), Ok(message.clone()));
+		let nonce = [0; OCB_NONCE_SIZE];
+		prop_assert_eq!(ocb_decrypt(key, &nonce, &[], &ocb_encrypt(key, &nonce, &[], &message)), Ok(message.clone()));
+		prop_assert_eq!(aes_unwrap(key, &aes_wrap(key, &key)), Ok(key.to_vec()));
+	}
+
+	#[test]
+	fn decrypting_arbitrary_bytes_never_panics(data in prop::collection::vec(any::<u8>(), 0..4 * BLOCK_SIZE), key in any::<[u8; 16]>()) {
+		let _ = ecb_decrypt(data.clone(), key);
+		let _ = cbc_decrypt(data.clone(), key);
+		let _ = ctr_decrypt(data.clone(), key);
+		let _ = gcm_decrypt(data.clone(), key, &[]);
+		let _ = ocb_decrypt(key, &[0; OCB_NONCE_SIZE], &[], &data);
+		let _ = aes_unwrap(key, &data);
+	}
+}
@@ -0,0 +1,118 @@
+use std::io::{self, Cursor, ErrorKind, Read, Write};
+
+use aes_modes::{
+	cbc_decrypt, cbc_encrypt, cbc_encrypt_stream, ctr_decrypt, ctr_encrypt_stream, ecb_decrypt, ecb_encrypt,
+	ecb_encrypt_stream, DecryptError, EncryptedReader, EncryptedWriter, BLOCK_SIZE,
+};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+/// Lengths around the block boundaries, plus one longer than the stream's internal buffer.
+const LENGTHS: [usize; 8] = [0, 1, 15, 16, 17, 32, 100, 10_000];
+
+fn message(len: usize) -> Vec<u8> {
+	(0..len).map(|i| i as u8).collect()
+}
+
+/// Hands out a single byte per read, to make sure short reads aren't taken as end of stream.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		match (self.0.split_first(), buf.first_mut()) {
+			(Some((&byte, rest)), Some(slot)) => {
+				*slot = byte;
+				self.0 = rest;
+				Ok(1)
+			}
+			_ => Ok(0),
+		}
+	}
+}
+
+#[test]
+fn streamed_ecb_matches_the_in_memory_function() {
+	for len in LENGTHS {
+		let mut cipher_text = Vec::new();
+		ecb_encrypt_stream(Cursor::new(message(len)), &mut cipher_text, KEY).unwrap();
+
+		assert_eq!(cipher_text, ecb_encrypt(message(len), KEY), "{len}");
+	}
+}
+
+#[test]
+fn streamed_output_decrypts_in_memory() {
+	for len in LENGTHS {
+		let (mut ecb, mut cbc, mut ctr) = (Vec::new(), Vec::new(), Vec::new());
+		ecb_encrypt_stream(Cursor::new(message(len)), &mut ecb, KEY).unwrap();
+		cbc_encrypt_stream(Cursor::new(message(len)), &mut cbc, KEY).unwrap();
+		ctr_encrypt_stream(Cursor::new(message(len)), &mut ctr, KEY).unwrap();
+
+		assert_eq!(ecb_decrypt(ecb, KEY).unwrap(), message(len), "ecb {len}");
+		assert_eq!(cbc_decrypt(cbc, KEY).unwrap(), message(len), "cbc {len}");
+		assert_eq!(ctr_decrypt(ctr, KEY).unwrap(), message(len), "ctr {len}");
+	}
+}
+
+#[test]
+fn short_reads_are_not_the_end_of_the_stream() {
+	let data = message(BLOCK_SIZE * 3 + 5);
+	let mut cipher_text = Vec::new();
+
+	cbc_encrypt_stream(Trickle(&data), &mut cipher_text, KEY).unwrap();
+
+	assert_eq!(cbc_decrypt(cipher_text, KEY).unwrap(), data);
+}
+
+#[test]
+fn adapters_round_trip_through_io_copy() {
+	for len in LENGTHS {
+		let mut cbc = EncryptedWriter::cbc(Vec::new(), KEY).unwrap();
+		let mut ctr = EncryptedWriter::ctr(Vec::new(), KEY).unwrap();
+		io::copy(&mut Cursor::new(message(len)), &mut cbc).unwrap();
+		io::copy(&mut Cursor::new(message(len)), &mut ctr).unwrap();
+		let (cbc, ctr) = (cbc.finish().unwrap(), ctr.finish().unwrap());
+
+		let mut plain_text = Vec::new();
+		io::copy(&mut EncryptedReader::cbc(Trickle(&cbc), KEY), &mut plain_text).unwrap();
+		assert_eq!(plain_text, message(len), "cbc {len}");
+
+		plain_text.clear();
+		io::copy(&mut EncryptedReader::ctr(Trickle(&ctr), KEY), &mut plain_text).unwrap();
+		assert_eq!(plain_text, message(len), "ctr {len}");
+
+		// The same format as the in-memory functions, header included.
+		assert_eq!(cbc_decrypt(cbc, KEY).unwrap(), message(len), "cbc {len}");
+		assert_eq!(ctr_decrypt(ctr, KEY).unwrap(), message(len), "ctr {len}");
+	}
+}
+
+#[test]
+fn writer_holds_back_only_the_partial_block() {
+	let mut writer = EncryptedWriter::ctr(Vec::new(), KEY).unwrap();
+	for byte in message(BLOCK_SIZE + 3) {
+		writer.write_all(&[byte]).unwrap();
+	}
+	writer.flush().unwrap();
+
+	let cipher_text = writer.finish().unwrap();
+	assert_eq!(cipher_text.len(), BLOCK_SIZE / 2 + BLOCK_SIZE + 3);
+	assert_eq!(ctr_decrypt(cipher_text, KEY).unwrap(), message(BLOCK_SIZE + 3));
+}
+
+#[test]
+fn reader_rejects_what_cbc_decrypt_rejects() {
+	let read_all = |cipher_text: &[u8]| {
+		let error = EncryptedReader::cbc(cipher_text, KEY).read_to_end(&mut Vec::new()).unwrap_err();
+		assert_eq!(error.kind(), ErrorKind::InvalidData);
+		*error.into_inner().unwrap().downcast::<DecryptError>().unwrap()
+	};
+
+	let cipher_text = cbc_encrypt(message(20), KEY);
+	assert_eq!(read_all(&[]), DecryptError::Empty);
+	assert_eq!(read_all(&cipher_text[..BLOCK_SIZE]), DecryptError::Empty);
+	assert_eq!(read_all(&cipher_text[..cipher_text.len() - 1]), DecryptError::InvalidLength);
+
+	// Dropping the last block leaves the first one, which ends in plaintext, not padding.
+	assert_eq!(read_all(&cipher_text[..2 * BLOCK_SIZE]), DecryptError::InvalidPadding);
+}
@@ -0,0 +1,37 @@
+//! With `test-rng`, seeding the IV generator makes the random-IV modes reproducible.
+#![cfg(feature = "test-rng")]
+
+use aes_modes::{cbc_encrypt, ctr_encrypt, gcm_encrypt, set_test_seed, BLOCK_SIZE};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+#[test]
+fn the_same_seed_gives_the_same_ciphertext() {
+	let message = b"hello world".to_vec();
+
+	let encrypt_all = || {
+		set_test_seed(42);
+		(cbc_encrypt(message.clone(), KEY), ctr_encrypt(message.clone(), KEY), gcm_encrypt(message.clone(), KEY, &[]))
+	};
+
+	assert_eq!(encrypt_all(), encrypt_all());
+}
+
+#[test]
+fn different_seeds_give_different_ciphertext() {
+	set_test_seed(1);
+	let first = cbc_encrypt(b"hello world".to_vec(), KEY);
+	set_test_seed(2);
+	let second = cbc_encrypt(b"hello world".to_vec(), KEY);
+
+	assert_ne!(first, second);
+}
+
+#[test]
+fn ivs_stay_random_until_a_seed_is_set() {
+	// Fresh threads, which have never been seeded.
+	let first = std::thread::spawn(|| cbc_encrypt(b"hello world".to_vec(), KEY)).join().unwrap();
+	let second = std::thread::spawn(|| cbc_encrypt(b"hello world".to_vec(), KEY)).join().unwrap();
+
+	assert_ne!(first, second);
+}
@@ -0,0 +1,150 @@
+//! The `tracing` spans must record the mode, the input length and the block count, and
+//! nothing else: no key, IV or data bytes.
+#![cfg(feature = "tracing")]
+
+use std::sync::{Arc, Mutex};
+
+use aes_modes::{
+	cbc_decrypt, cbc_encrypt, cbc_encrypt_stream, ctr_decrypt, ctr_encrypt, ctr_encrypt_stream, ecb_decrypt, ecb_encrypt,
+	ecb_encrypt_stream, gcm_decrypt, gcm_encrypt, ocb_decrypt, ocb_encrypt, BLOCK_SIZE,
+};
+use tracing::{
+	field::{Field, Visit},
+	span, Event, Metadata, Subscriber,
+};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+/// A span as the subscriber saw it: its name and every field with its value.
+#[derive(Debug, PartialEq)]
+struct RecordedSpan {
+	name: &'static str,
+	fields: Vec<(&'static str, String)>,
+}
+
+/// Keeps every span created and every field recorded on one afterwards. It also tracks which
+/// spans are entered, so `Span::current` works as it does under a real subscriber.
+#[derive(Default)]
+struct Capture {
+	spans: Arc<Mutex<Vec<RecordedSpan>>>,
+	metadata: Mutex<Vec<&'static Metadata<'static>>>,
+	entered: Mutex<Vec<span::Id>>,
+}
+
+struct FieldVisitor<'a>(&'a mut Vec<(&'static str, String)>);
+
+impl Visit for FieldVisitor<'_> {
+	fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+		self.0.push((field.name(), format!("{value:?}")));
+	}
+}
+
+impl Subscriber for Capture {
+	fn enabled(&self, _: &Metadata<'_>) -> bool {
+		true
+	}
+
+	fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+		let mut fields = Vec::new();
+		attributes.record(&mut FieldVisitor(&mut fields));
+		let mut spans = self.spans.lock().unwrap();
+		spans.push(RecordedSpan { name: attributes.metadata().name(), fields });
+		self.metadata.lock().unwrap().push(attributes.metadata());
+
+		// Span ids must be non-zero, so span n gets id n + 1.
+		span::Id::from_u64(spans.len() as u64)
+	}
+
+	fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+		let mut spans = self.spans.lock().unwrap();
+		values.record(&mut FieldVisitor(&mut spans[id.into_u64() as usize - 1].fields));
+	}
+
+	fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+	fn event(&self, _: &Event<'_>) {}
+
+	fn enter(&self, id: &span::Id) {
+		self.entered.lock().unwrap().push(id.clone());
+	}
+
+	fn exit(&self, _: &span::Id) {
+		self.entered.lock().unwrap().pop();
+	}
+
+	fn current_span(&self) -> tracing_core::span::Current {
+		match self.entered.lock().unwrap().last() {
+			Some(id) => tracing_core::span::Current::new(id.clone(), self.metadata.lock().unwrap()[id.into_u64() as usize - 1]),
+			None => tracing_core::span::Current::none(),
+		}
+	}
+}
+
+/// Runs `f` with a capturing subscriber installed on this thread and returns its spans.
+fn capture(f: impl FnOnce()) -> Vec<RecordedSpan> {
+	let subscriber = Capture::default();
+	let spans = Arc::clone(&subscriber.spans);
+	tracing::subscriber::with_default(subscriber, f);
+
+	Arc::try_unwrap(spans).unwrap().into_inner().unwrap()
+}
+
+fn span(name: &'static str, mode: &str, input_len: usize, blocks: usize) -> RecordedSpan {
+	RecordedSpan {
+		name,
+		fields: vec![("mode", format!("{mode:?}")), ("input_len", input_len.to_string()), ("blocks", blocks.to_string())],
+	}
+}
+
+#[test]
+fn spans_record_only_the_mode_and_lengths() {
+	let message = b"a message that is a few blocks of plaintext".to_vec();
+	assert_eq!(message.len(), 43);
+
+	let spans = capture(|| {
+		ecb_decrypt(ecb_encrypt(message.clone(), KEY), KEY).unwrap();
+		cbc_decrypt(cbc_encrypt(message.clone(), KEY), KEY).unwrap();
+		ctr_decrypt(ctr_encrypt(message.clone(), KEY), KEY).unwrap();
+	});
+
+	assert_eq!(
+		spans,
+		[
+			span("ecb_encrypt", "ecb", 43, 3),
+			span("ecb_decrypt", "ecb", 48, 3),
+			span("cbc_encrypt", "cbc", 43, 3),
+			span("cbc_decrypt", "cbc", 64, 3),
+			span("ctr_encrypt", "ctr", 43, 3),
+			span("ctr_decrypt", "ctr", 51, 3),
+		]
+	);
+}
+
+#[test]
+fn aead_and_stream_spans_record_the_same_fields() {
+	let message = b"a message that is a few blocks of plaintext".to_vec();
+	let nonce = [7u8; 12];
+
+	let spans = capture(|| {
+		gcm_decrypt(gcm_encrypt(message.clone(), KEY, b"aad"), KEY, b"aad").unwrap();
+		ocb_decrypt(KEY, &nonce, b"aad", &ocb_encrypt(KEY, &nonce, b"aad", &message)).unwrap();
+
+		let mut sink = Vec::new();
+		ecb_encrypt_stream(&message[..], &mut sink, KEY).unwrap();
+		cbc_encrypt_stream(&message[..], &mut sink, KEY).unwrap();
+		ctr_encrypt_stream(&message[..], &mut sink, KEY).unwrap();
+	});
+
+	assert_eq!(
+		spans,
+		[
+			span("gcm_encrypt", "gcm", 43, 3),
+			span("gcm_decrypt", "gcm", 71, 3),
+			span("ocb_encrypt", "ocb", 43, 3),
+			span("ocb_decrypt", "ocb", 59, 3),
+			span("ecb_encrypt_stream", "ecb", 43, 3),
+			span("cbc_encrypt_stream", "cbc", 43, 3),
+			span("ctr_encrypt_stream", "ctr", 43, 3),
+		]
+	);
+}
@@ -0,0 +1,39 @@
+//! The `*_decrypt_zeroizing` variants return the same plaintext as the plain ones.
+#![cfg(feature = "zeroize")]
+
+use aes_modes::{
+	cbc_decrypt_zeroizing, cbc_encrypt, ctr_decrypt_zeroizing, ctr_encrypt, ecb_decrypt_zeroizing, ecb_encrypt,
+	DecryptError, BLOCK_SIZE,
+};
+
+const KEY: [u8; BLOCK_SIZE] = *b"YELLOW SUBMARINE";
+
+const MESSAGE: &[u8] = b"a message that spans a few blocks of plaintext";
+
+#[test]
+fn ecb_decrypt_zeroizing_round_trips() {
+	let plain_text = ecb_decrypt_zeroizing(ecb_encrypt(MESSAGE.to_vec(), KEY), KEY).unwrap();
+
+	assert_eq!(*plain_text, MESSAGE);
+}
+
+#[test]
+fn cbc_decrypt_zeroizing_round_trips() {
+	let plain_text = cbc_decrypt_zeroizing(cbc_encrypt(MESSAGE.to_vec(), KEY), KEY).unwrap();
+
+	assert_eq!(*plain_text, MESSAGE);
+}
+
+#[test]
+fn ctr_decrypt_zeroizing_round_trips() {
+	let plain_text = ctr_decrypt_zeroizing(ctr_encrypt(MESSAGE.to_vec(), KEY), KEY).unwrap();
+
+	assert_eq!(*plain_text, MESSAGE);
+}
+
+#[test]
+fn zeroizing_variants_pass_errors_through() {
+	assert_eq!(ecb_decrypt_zeroizing(vec![0; 3], KEY).unwrap_err(), DecryptError::InvalidLength);
+	assert_eq!(cbc_decrypt_zeroizing(Vec::new(), KEY).unwrap_err(), DecryptError::Empty);
+	assert_eq!(ctr_decrypt_zeroizing(vec![0; 3], KEY).unwrap_err(), DecryptError::InvalidLength);
+}